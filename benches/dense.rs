@@ -0,0 +1,115 @@
+use std::hint::black_box;
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use rust_hll::{DenseRegisters, Hll, Settings, Storage};
+
+fn full_settings() -> Settings {
+    Settings::new(20, 6, 0, false).unwrap()
+}
+
+fn clear(c: &mut Criterion) {
+    let settings = full_settings();
+    let mut registers = DenseRegisters::with_settings(&settings);
+    for i in 0..settings.registers() {
+        registers.set_reg(i, (i % 45) as u8);
+    }
+    // the same registers as the buffer the loop clears
+    let mut bytes = vec![0; registers.bytes_size()];
+    registers.to_bytes(&mut bytes);
+    let mut group = c.benchmark_group("dense_clear");
+
+    group.bench_function("fill", |b| {
+        b.iter_batched_ref(
+            || registers.clone(),
+            |registers| {
+                registers.clear();
+                black_box(registers);
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    // the byte-by-byte loop clear used to be implemented with
+    #[allow(clippy::needless_range_loop)]
+    group.bench_function("loop", |b| {
+        b.iter_batched_ref(
+            || bytes.clone(),
+            |buf| {
+                for i in 0..buf.len() {
+                    buf[i] = 0;
+                }
+                black_box(buf);
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+fn indicator(c: &mut Criterion) {
+    let settings = Settings::new(14, 6, 0, false).unwrap();
+    let mut registers = DenseRegisters::with_settings(&settings);
+    for i in 0..(1 << 14) {
+        registers.set_reg(i, (i % 20) as u8);
+    }
+    let hll = Hll::from(registers.clone());
+    let mut group = c.benchmark_group("dense_indicator");
+
+    group.bench_function("table", |b| b.iter(|| black_box(hll.cardinality())));
+
+    // the per register division the indicator used to be implemented with
+    group.bench_function("division", |b| {
+        b.iter(|| {
+            let sum: f64 = registers
+                .iter()
+                .map(|(_, value)| 1.0 / ((1_u64 << value) as f64))
+                .sum();
+            black_box(sum)
+        })
+    });
+
+    group.finish();
+}
+
+fn union(c: &mut Criterion) {
+    let settings = Settings::new(14, 6, 0, false).unwrap();
+    let mut registers = DenseRegisters::with_settings(&settings);
+    let mut other = DenseRegisters::with_settings(&settings);
+    for i in 0..(1 << 14) {
+        registers.set_reg(i, (i % 20) as u8);
+        other.set_reg(i, (i % 23) as u8);
+    }
+    let mut group = c.benchmark_group("dense_union");
+
+    group.bench_function("packed", |b| {
+        b.iter_batched_ref(
+            || registers.clone(),
+            |registers| black_box(registers.union_dense(&other).unwrap()),
+            BatchSize::LargeInput,
+        )
+    });
+
+    // the per register loop union_dense falls back to for registers of different widths
+    group.bench_function("loop", |b| {
+        b.iter_batched_ref(
+            || registers.clone(),
+            |registers| {
+                let mut changed = 0;
+                for (reg_num, value) in other.iter() {
+                    if value > registers.get(reg_num) {
+                        registers.set_reg(reg_num, value);
+                        changed += 1;
+                    }
+                }
+                black_box(changed)
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, clear, indicator, union);
+criterion_main!(benches);
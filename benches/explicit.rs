@@ -0,0 +1,99 @@
+use std::hint::black_box;
+
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use rust_hll::{Hll, Settings};
+
+/// settings with the default explicit threshold of 1,536 values and one near the spec maximum of
+/// 98,304 values
+fn explicit_settings() -> [Settings; 2] {
+    [
+        Settings::new(14, 6, -1, true).unwrap(),
+        Settings::new(20, 6, -1, true).unwrap(),
+    ]
+}
+
+/// random hashed values that fill the explicit representation up to its threshold
+fn explicit_values(settings: &Settings, seed: u64) -> Vec<u64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    (0..settings.explicit_threshold())
+        .map(|_| rng.random())
+        .collect()
+}
+
+fn explicit_hll(settings: &Settings, values: &[u64]) -> Hll {
+    let mut hll = Hll::new(*settings);
+    hll.add_raw_all(values);
+    hll
+}
+
+fn insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("explicit_insert");
+
+    for settings in explicit_settings() {
+        let values = explicit_values(&settings, 1);
+        let threshold = settings.explicit_threshold();
+
+        group.bench_with_input(BenchmarkId::new("fill", threshold), &values, |b, values| {
+            b.iter(|| black_box(explicit_hll(&settings, values)))
+        });
+        group.bench_with_input(BenchmarkId::new("one", threshold), &values, |b, values| {
+            b.iter(|| black_box(explicit_hll(&settings, &values[..1])))
+        });
+    }
+
+    group.finish();
+}
+
+fn union(c: &mut Criterion) {
+    let mut group = c.benchmark_group("explicit_union");
+
+    for settings in explicit_settings() {
+        let values_a = explicit_values(&settings, 1);
+        let values_b = explicit_values(&settings, 2);
+        let lhs = explicit_hll(&settings, &values_a[..values_a.len() / 2]);
+        let rhs = explicit_hll(&settings, &values_b[..values_b.len() / 2]);
+
+        group.bench_function(
+            BenchmarkId::from_parameter(settings.explicit_threshold()),
+            |b| {
+                b.iter_batched_ref(
+                    || lhs.clone(),
+                    |lhs| black_box(lhs.union(true, &rhs)),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn serialize(c: &mut Criterion) {
+    let mut to_bytes = c.benchmark_group("explicit_to_bytes");
+    for settings in explicit_settings() {
+        let hll = explicit_hll(&settings, &explicit_values(&settings, 1));
+
+        to_bytes.bench_function(
+            BenchmarkId::from_parameter(settings.explicit_threshold()),
+            |b| b.iter(|| black_box(hll.to_bytes())),
+        );
+    }
+    to_bytes.finish();
+
+    let mut from_bytes = c.benchmark_group("explicit_from_bytes");
+    for settings in explicit_settings() {
+        let bytes = explicit_hll(&settings, &explicit_values(&settings, 1)).to_bytes();
+
+        from_bytes.bench_function(
+            BenchmarkId::from_parameter(settings.explicit_threshold()),
+            |b| b.iter(|| black_box(Hll::from_bytes(&bytes))),
+        );
+    }
+    from_bytes.finish();
+}
+
+criterion_group!(benches, insert, union, serialize);
+criterion_main!(benches);
@@ -0,0 +1,63 @@
+use std::hint::black_box;
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use rust_hll::{Hll, Settings, SparseRegisters};
+
+fn sparse_settings() -> Settings {
+    Settings::new(14, 6, 0, true).unwrap()
+}
+
+/// random hashed values that fill the sparse representation up to its threshold
+fn sparse_values(seed: u64) -> Vec<u64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut hll = Hll::new(sparse_settings());
+    let mut values = Vec::new();
+
+    loop {
+        let value = rng.random();
+        hll.add_raw(value);
+        if !matches!(hll, Hll::Sparse(_)) {
+            return values;
+        }
+
+        values.push(value);
+    }
+}
+
+fn sparse_hll(values: &[u64]) -> SparseRegisters {
+    let mut hll = Hll::new(sparse_settings());
+    hll.add_raw_all(values);
+
+    match hll {
+        Hll::Sparse(sparse_registers) => sparse_registers,
+        _ => unreachable!("values were chosen to stay sparse"),
+    }
+}
+
+fn insert(c: &mut Criterion) {
+    let values = sparse_values(1);
+
+    c.bench_function("sparse_insert", |b| {
+        b.iter(|| black_box(sparse_hll(&values)))
+    });
+}
+
+fn union(c: &mut Criterion) {
+    let values_a = sparse_values(1);
+    let values_b = sparse_values(2);
+    let lhs = sparse_hll(&values_a[..values_a.len() / 2]);
+    let rhs = sparse_hll(&values_b[..values_b.len() / 2]);
+
+    c.bench_function("sparse_union", |b| {
+        b.iter_batched_ref(
+            || lhs.clone(),
+            |lhs| black_box(lhs.union_sparse(&rhs)),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, insert, union);
+criterion_main!(benches);
@@ -0,0 +1,169 @@
+//! Generates the HyperLogLog++ bias correction tables in `src/bias_data.rs`:
+//!
+//!     cargo run --release --example bias_tables > src/bias_data.rs && cargo fmt
+//!
+//! This follows the method of Heule et al.: for each log_2m, many sketches are filled with random
+//! hashes and the raw estimate is averaged at evenly spaced cardinalities up to 5m.  The bias at a
+//! cardinality is the mean raw estimate minus the cardinality.
+//!
+//! The tables published with the paper can be imported instead:
+//!
+//!     cargo run --example bias_tables -- --import raw_estimates.txt bias.txt > src/bias_data.rs
+//!
+//! Each file holds one table per log_2m from 4 to 18 as nested arrays, e.g. the `rawEstimateData`
+//! and `biasData` arrays of the paper's appendix as `{ {11, 11.717, ...}, {23, 23.1194, ...} }`.
+
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+const MIN_LOG_2M: u32 = 4;
+const MAX_LOG_2M: u32 = 18;
+const REG_WIDTH: u32 = 5;
+const POINTS: usize = 80;
+
+fn alpha_m_squared(log_2m: u32) -> f64 {
+    let m = (1u64 << log_2m) as f64;
+
+    match log_2m {
+        4 => 0.673 * m * m,
+        5 => 0.697 * m * m,
+        6 => 0.709 * m * m,
+        _ => (0.7213 / (1.0 + 1.079 / m)) * m * m,
+    }
+}
+
+/// table returns the mean raw estimate and the mean bias at each of the sampled cardinalities.
+fn table(log_2m: u32, rng: &mut StdRng) -> (Vec<f64>, Vec<f64>) {
+    let m = 1usize << log_2m;
+    let pw_max_mask = !((1u64 << ((1u64 << REG_WIDTH) - 2)) - 1);
+    let step = 5 * m / POINTS;
+    let trials = (200).max((1 << 22) / m);
+    let alpha_m_squared = alpha_m_squared(log_2m);
+
+    let mut estimates = vec![0.0; POINTS];
+    let mut registers = vec![0u8; m];
+    for _ in 0..trials {
+        registers.fill(0);
+        let mut sum = m as f64;
+
+        for n in 1..=(POINTS * step) {
+            let value: u64 = rng.random();
+            let substream_value = value >> log_2m;
+            if substream_value != 0 {
+                let p_w = (1 + (substream_value | pw_max_mask).trailing_zeros()) as u8;
+                let register = &mut registers[value as usize & (m - 1)];
+                if p_w > *register {
+                    sum += 2f64.powi(-(p_w as i32)) - 2f64.powi(-(*register as i32));
+                    *register = p_w;
+                }
+            }
+
+            if n % step == 0 {
+                estimates[n / step - 1] += alpha_m_squared / sum;
+            }
+        }
+    }
+
+    let raw_estimates: Vec<f64> = estimates.iter().map(|e| e / trials as f64).collect();
+    let bias = raw_estimates
+        .iter()
+        .enumerate()
+        .map(|(i, e)| e - ((i + 1) * step) as f64)
+        .collect();
+
+    (raw_estimates, bias)
+}
+
+fn print_table(name: &str, tables: &[Vec<f64>], precision: usize) {
+    println!("pub(crate) static {}: [&[f64]; {}] = [", name, tables.len());
+    for table in tables {
+        println!("    &[");
+        for chunk in table.chunks(8) {
+            let values: Vec<String> = chunk
+                .iter()
+                .map(|v| format!("{:.*}", precision, v))
+                .collect();
+            println!("        {},", values.join(", "));
+        }
+        println!("    ],");
+    }
+    println!("];");
+}
+
+/// read_tables parses nested arrays of numbers, one inner array per log_2m.  Any of `{}[]()` can
+/// delimit the arrays so tables copied from C, Java or Go sources can be used as is.
+fn read_tables(path: &str) -> Vec<Vec<f64>> {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|err| panic!("{}: {}", path, err));
+    let text = text.replace(['{', '[', '('], " ");
+
+    let tables: Vec<Vec<f64>> = text
+        .split(['}', ']', ')'])
+        .map(|table| {
+            table
+                .split([',', ' ', '\n', '\r', '\t'])
+                .filter(|value| !value.is_empty())
+                .map(|value| {
+                    value
+                        .parse()
+                        .unwrap_or_else(|_| panic!("{}: {:?}", path, value))
+                })
+                .collect::<Vec<f64>>()
+        })
+        .filter(|table| !table.is_empty())
+        .collect();
+
+    let expected = (MAX_LOG_2M - MIN_LOG_2M + 1) as usize;
+    assert_eq!(expected, tables.len(), "{}: one table per log_2m", path);
+    tables
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (raw_estimates, bias, precision, source): (Vec<_>, Vec<_>, _, _) = match args.as_slice() {
+        [flag, raw_estimates, bias] if flag == "--import" => {
+            let (raw_estimates, bias) = (read_tables(raw_estimates), read_tables(bias));
+            for (raw_estimates, bias) in raw_estimates.iter().zip(bias.iter()) {
+                assert_eq!(
+                    raw_estimates.len(),
+                    bias.len(),
+                    "tables must be the same size"
+                );
+            }
+
+            (
+                raw_estimates,
+                bias,
+                4,
+                "These are the tables published by Heule et al.",
+            )
+        }
+        [] => {
+            let mut rng = StdRng::seed_from_u64(0);
+            let (raw_estimates, bias) = (MIN_LOG_2M..=MAX_LOG_2M)
+                .map(|log_2m| table(log_2m, &mut rng))
+                .unzip();
+
+            (
+                raw_estimates,
+                bias,
+                3,
+                "These are measured with this crate's estimator, not copied from the tables published by Heule et al.",
+            )
+        }
+        _ => panic!("usage: bias_tables [--import RAW_ESTIMATES BIAS]"),
+    };
+
+    println!(
+        "// generated by `examples/bias_tables.rs`, do not edit.  {}",
+        source
+    );
+    println!();
+    println!(
+        "/// mean raw estimates at sampled cardinalities up to 5m, for log_2m {} to {}.",
+        MIN_LOG_2M, MAX_LOG_2M
+    );
+    print_table("RAW_ESTIMATES", &raw_estimates, precision);
+    println!();
+    println!("/// mean bias of the raw estimate at each entry of RAW_ESTIMATES.");
+    print_table("BIAS", &bias, precision);
+}
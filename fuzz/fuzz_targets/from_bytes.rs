@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_hll::Hll;
+
+// Deserializing untrusted bytes must return an error rather than panic.  Inputs that used to panic
+// are kept in fuzz/regressions/from_bytes and are also run by the crate's unit tests.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(hll) = Hll::from_bytes(data) {
+        hll.cardinality();
+        hll.to_bytes();
+    }
+    let _ = Hll::cardinality_from_bytes(data);
+});
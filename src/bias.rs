@@ -0,0 +1,110 @@
+use crate::Settings;
+use crate::bias_data::{BIAS, RAW_ESTIMATES};
+use crate::math;
+
+// range of log_2m covered by the bias tables
+const MINIMUM_LOG_2M: u32 = 4;
+const MAXIMUM_LOG_2M: u32 = 18;
+
+// the tables were generated with 5 bit registers.  Narrower registers saturate at small enough
+// cardinalities to change the bias.
+const MINIMUM_REG_WIDTH: u32 = 4;
+
+// cardinalities below which linear counting beats the bias corrected estimate, for log_2m 4 to 18.
+// See the appendix of "HyperLogLog in Practice" (Heule et al.).
+const LINEAR_COUNTING_THRESHOLDS: [f64; 15] = [
+    10.0, 20.0, 40.0, 80.0, 220.0, 400.0, 900.0, 1800.0, 3100.0, 6500.0, 11500.0, 20000.0, 50000.0,
+    120000.0, 350000.0,
+];
+
+/// supported returns true if the bias tables cover the settings.
+pub(crate) fn supported(settings: &Settings) -> bool {
+    (MINIMUM_LOG_2M..=MAXIMUM_LOG_2M).contains(&settings.log_2m)
+        && settings.reg_width >= MINIMUM_REG_WIDTH
+}
+
+/// estimate applies the HyperLogLog++ small range correction to the raw estimator.  It returns None
+/// when the estimator is past the range of the bias tables (5m) or the settings aren't supported,
+/// in which case the standard corrections apply.
+pub(crate) fn estimate(settings: &Settings, estimator: f64, num_of_zeros: u32) -> Option<f64> {
+    let m = (1_u64 << settings.log_2m) as f64;
+    if !supported(settings) || estimator > 5.0 * m {
+        return None;
+    }
+
+    let idx = (settings.log_2m - MINIMUM_LOG_2M) as usize;
+    if num_of_zeros != 0 {
+        let linear_count = m * math::ln(m / num_of_zeros as f64);
+        if linear_count <= LINEAR_COUNTING_THRESHOLDS[idx] {
+            return Some(linear_count);
+        }
+    }
+
+    Some(estimator - bias(RAW_ESTIMATES[idx], BIAS[idx], estimator))
+}
+
+/// bias linearly interpolates the bias of the estimator between the closest sampled raw estimates.
+fn bias(raw_estimates: &[f64], bias: &[f64], estimator: f64) -> f64 {
+    let i = raw_estimates.partition_point(|e| *e < estimator);
+    if i == 0 {
+        return bias[0];
+    }
+    if i == raw_estimates.len() {
+        return bias[i - 1];
+    }
+
+    let (lo, hi) = (raw_estimates[i - 1], raw_estimates[i]);
+    let t = (estimator - lo) / (hi - lo);
+    bias[i - 1] + t * (bias[i] - bias[i - 1])
+}
+
+#[cfg(test)]
+mod test {
+    use rand::prelude::*;
+    use rand::rngs::StdRng;
+
+    use super::bias;
+    use crate::{Hll, Settings};
+
+    #[test]
+    fn interpolation() {
+        let raw_estimates = [10.0, 20.0, 30.0];
+        let biases = [5.0, 3.0, 1.0];
+
+        assert_eq!(5.0, bias(&raw_estimates, &biases, 1.0));
+        assert_eq!(5.0, bias(&raw_estimates, &biases, 10.0));
+        assert_eq!(4.0, bias(&raw_estimates, &biases, 15.0));
+        assert_eq!(1.5, bias(&raw_estimates, &biases, 27.5));
+        assert_eq!(1.0, bias(&raw_estimates, &biases, 100.0));
+    }
+
+    #[test]
+    fn lower_error() {
+        let settings = Settings::new(11, 5, 0, false).unwrap();
+        let corrected_settings = settings.with_bias_correction(true);
+        let m = 1 << settings.log_2m;
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let (mut error, mut corrected_error) = (0.0, 0.0);
+        for cardinality in (1..=10).map(|i| i * m / 2) {
+            for _ in 0..20 {
+                let mut hll = Hll::new(settings);
+                for _ in 0..cardinality {
+                    hll.add_raw(rng.random());
+                }
+
+                let actual = cardinality as f64;
+                let corrected = hll.clone_with_settings(&corrected_settings);
+                error += (hll.cardinality() as f64 - actual).abs() / actual;
+                corrected_error += (corrected.cardinality() as f64 - actual).abs() / actual;
+            }
+        }
+
+        assert!(
+            corrected_error < error,
+            "corrected error {} not below {}",
+            corrected_error,
+            error
+        );
+    }
+}
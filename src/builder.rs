@@ -0,0 +1,160 @@
+use crate::{Hll, Settings, SettingsError};
+
+/// `SettingsBuilder` configures `Settings` by name rather than by position.  The defaults are
+/// log_2m 11, reg_width 5, an automatically calculated explicit threshold and sparse enabled.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SettingsBuilder {
+    log_2m: u32,
+    reg_width: u32,
+    explicit_threshold: i32,
+    sparse_enabled: bool,
+    bias_correction: bool,
+}
+
+impl Default for SettingsBuilder {
+    fn default() -> Self {
+        Self {
+            log_2m: 11,
+            reg_width: 5,
+            explicit_threshold: -1,
+            sparse_enabled: true,
+            bias_correction: false,
+        }
+    }
+}
+
+impl SettingsBuilder {
+    pub fn log_2m(mut self, log_2m: u32) -> Self {
+        self.log_2m = log_2m;
+        self
+    }
+
+    pub fn reg_width(mut self, reg_width: u32) -> Self {
+        self.reg_width = reg_width;
+        self
+    }
+
+    /// explicit_threshold sets the cardinality at which explicit storage is abandoned.  0 disables
+    /// explicit storage and -1 calculates the threshold from the other settings.
+    pub fn explicit_threshold(mut self, explicit_threshold: i32) -> Self {
+        self.explicit_threshold = explicit_threshold;
+        self
+    }
+
+    /// sparse enables sparse storage.  It stays disabled if log_2m plus reg_width is more than 32,
+    /// since sparse entries wouldn't fit in 32 bits.
+    pub fn sparse(mut self, sparse_enabled: bool) -> Self {
+        self.sparse_enabled = sparse_enabled;
+        self
+    }
+
+    /// bias_correction enables the HyperLogLog++ bias correction, see
+    /// `Settings::with_bias_correction`.
+    pub fn bias_correction(mut self, enabled: bool) -> Self {
+        self.bias_correction = enabled;
+        self
+    }
+
+    pub fn build(self) -> Result<Settings, SettingsError> {
+        Ok(Settings::new(
+            self.log_2m,
+            self.reg_width,
+            self.explicit_threshold,
+            self.sparse_enabled,
+        )?
+        .with_bias_correction(self.bias_correction))
+    }
+}
+
+/// `HllBuilder` constructs an `Hll` and seeds it with data in a single expression.
+#[derive(Clone, Debug)]
+pub struct HllBuilder {
+    hll: Hll,
+}
+
+impl HllBuilder {
+    pub fn new(settings: Settings) -> Self {
+        Self {
+            hll: Hll::new(settings),
+        }
+    }
+
+    /// add_raw_iter adds every already hashed value from the iterator to the Hll being built.
+    pub fn add_raw_iter<I: IntoIterator<Item = u64>>(mut self, values: I) -> Self {
+        for value in values {
+            self.hll.add_raw(value);
+        }
+
+        self
+    }
+
+    pub fn build(self) -> Hll {
+        self.hll
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{HllBuilder, SettingsBuilder};
+    use crate::{Hll, sparse_test::construct_hll_value};
+    use crate::{Settings, SettingsError};
+
+    #[test]
+    fn build_settings() {
+        assert_eq!(
+            Settings::new(11, 5, -1, true).unwrap(),
+            SettingsBuilder::default().build().unwrap()
+        );
+        assert_eq!(
+            Settings::new(14, 6, 0, false).unwrap(),
+            SettingsBuilder::default()
+                .log_2m(14)
+                .reg_width(6)
+                .explicit_threshold(0)
+                .sparse(false)
+                .build()
+                .unwrap()
+        );
+        assert_eq!(
+            Settings::new(11, 5, -1, true)
+                .unwrap()
+                .with_bias_correction(true),
+            SettingsBuilder::default()
+                .bias_correction(true)
+                .build()
+                .unwrap()
+        );
+        assert!(matches!(
+            SettingsBuilder::default().log_2m(3).build(),
+            Err(SettingsError::Log2m)
+        ));
+        assert!(matches!(
+            SettingsBuilder::default().reg_width(9).build(),
+            Err(SettingsError::RegWidth)
+        ));
+    }
+
+    #[test]
+    fn build() {
+        for explicit_threshold in [-1, 0] {
+            let settings = Settings::new(11, 5, explicit_threshold, true).unwrap();
+            let values: Vec<u64> = (0..2000)
+                .map(|i| construct_hll_value(settings.log_2m, i % 2048, i % 7 + 1))
+                .collect();
+
+            let mut expected = Hll::new(settings);
+            for value in values.iter() {
+                expected.add_raw(*value);
+            }
+
+            let hll = HllBuilder::new(settings)
+                .add_raw_iter(values.iter().copied())
+                .build();
+            assert_eq!(expected, hll);
+            assert_eq!(expected.cardinality(), hll.cardinality());
+        }
+
+        let settings = Settings::new(11, 5, -1, true).unwrap();
+        assert_eq!(Hll::new(settings), HllBuilder::new(settings).build());
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{Hll, Settings};
+
+/// count_distinct estimates the number of distinct items with an Hll sized by
+/// `Settings::for_error_rate` for target_error.  Items are hashed with `Hll::add_bytes`.
+///
+/// # Panics
+///
+/// Panics if target_error is not achievable, see `Settings::for_error_rate`.
+pub fn count_distinct<I>(items: I, target_error: f64) -> u64
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    let settings = match Settings::for_error_rate(target_error, 5, true) {
+        Ok(settings) => settings,
+        Err(err) => panic!("invalid target error {target_error}: {err}"),
+    };
+
+    let mut hll = Hll::new(settings);
+    for item in items {
+        hll.add_bytes(item.as_ref());
+    }
+
+    hll.cardinality()
+}
+
+#[cfg(test)]
+mod test {
+    use super::count_distinct;
+
+    #[test]
+    fn count_distinct_items() {
+        let items: Vec<String> = (0..10_000).map(|i| format!("item-{i}")).collect();
+
+        for target_error in [0.01, 0.05] {
+            // allow three standard errors
+            let estimate = count_distinct(items.iter().chain(items.iter()), target_error);
+            let error = (estimate as f64 - 10_000.0).abs() / 10_000.0;
+            assert!(error <= 3.0 * target_error, "estimate {}", estimate);
+        }
+
+        assert_eq!(0, count_distinct(Vec::<&[u8]>::new(), 0.01));
+        assert_eq!(3, count_distinct([b"a", b"b", b"a", b"c"], 0.01));
+    }
+
+    #[test]
+    #[should_panic]
+    fn count_distinct_unachievable() {
+        count_distinct([b"a"], 0.0);
+    }
+}
@@ -1,23 +1,41 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
 use crate::{
-    Registers, Settings, Storage,
+    HllError, RegisterIndex, RegisterValue, Registers, Settings, SettingsError, Storage,
     explicit::ExplicitStorage,
     sparse::SparseRegisters,
-    utils::{calc_position, divide_by_8_round_up, read_u8_bits, write_u8_bits},
+    utils::{calc_position, inverse_pow2, read_u8_bits, write_u8_bits},
 };
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct DenseRegisters {
     pub settings: Settings,
     buf: Vec<u8>,
+    #[cfg(feature = "touch-counts")]
+    touches: TouchCounts,
+}
+
+/// TouchCounts tracks how many times each register was the target of a `set` call. It is diagnostic
+/// only, so it is ignored when comparing registers.
+#[cfg(feature = "touch-counts")]
+#[derive(Debug, Clone, Default)]
+struct TouchCounts(Vec<u32>);
+
+#[cfg(feature = "touch-counts")]
+impl PartialEq for TouchCounts {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
 }
 
 impl DenseRegisters {
     pub fn with_settings(settings: &Settings) -> Self {
-        let cap = divide_by_8_round_up((1 << settings.log_2m) * settings.reg_width);
-
         Self {
             settings: *settings,
-            buf: vec![0; cap as usize],
+            buf: vec![0; settings.dense_bytes_size()],
+            #[cfg(feature = "touch-counts")]
+            touches: TouchCounts(vec![0; 1 << settings.log_2m]),
         }
     }
 
@@ -25,25 +43,67 @@ impl DenseRegisters {
         Self {
             settings: *settings,
             buf: self.buf.clone(),
+            #[cfg(feature = "touch-counts")]
+            touches: self.touches.clone(),
         }
     }
 
-    pub fn union_explicit(&mut self, explicit_storage: &ExplicitStorage) {
-        for i in explicit_storage.iter() {
-            self.set(i);
-        }
+    /// touch_counts returns how many times each register was the target of a `set` call.
+    #[cfg(feature = "touch-counts")]
+    pub fn touch_counts(&self) -> &[u32] {
+        &self.touches.0
     }
 
-    pub fn union_sparse(&mut self, sparse_storage: &SparseRegisters) {
-        for (reg_num, value) in sparse_storage.iter() {
-            self.set_if_greater(*reg_num, *value);
+    /// add_touch_counts adds the (reg_num, count) touch counts collected by other storage.
+    #[cfg(feature = "touch-counts")]
+    pub(crate) fn add_touch_counts(&mut self, counts: impl Iterator<Item = (u32, u32)>) {
+        for (reg_num, count) in counts {
+            self.touches.0[reg_num as usize] += count;
         }
     }
 
-    pub fn union_dense(&mut self, other: &Self) {
-        for (reg_num, value) in other.iter() {
-            self.set_if_greater(reg_num, value);
+    /// union_explicit returns the number of registers that were raised.
+    pub fn union_explicit(&mut self, explicit_storage: &ExplicitStorage) -> u32 {
+        explicit_storage.iter().filter(|i| self.set(*i)).count() as u32
+    }
+
+    /// union_sparse returns the number of registers that were raised.  Values too large for our
+    /// registers are clamped to the max register value.
+    pub fn union_sparse(&mut self, sparse_storage: &SparseRegisters) -> u32 {
+        let max_value = self.settings.max_register_value();
+
+        sparse_storage
+            .iter()
+            .filter(|(reg_num, value)| {
+                self.set_if_greater(
+                    RegisterIndex(*reg_num),
+                    RegisterValue((*value).min(max_value)),
+                )
+            })
+            .count() as u32
+    }
+
+    /// union_dense returns the number of registers that were raised.  Both operands must share
+    /// log_2m and other's registers can't be wider than ours, otherwise its registers would not line
+    /// up with this buffer.
+    pub fn union_dense(&mut self, other: &Self) -> Result<u32, SettingsError> {
+        self.settings.widening_check(&other.settings)?;
+
+        // registers of the same width line up byte for byte, so they can be merged a word at a time
+        if self.settings.reg_width == other.settings.reg_width {
+            return Ok(union_packed(
+                self.settings.reg_width as usize,
+                &mut self.buf,
+                &other.buf,
+            ));
         }
+
+        Ok(other
+            .iter()
+            .filter(|(reg_num, value)| {
+                self.set_if_greater(RegisterIndex(*reg_num), RegisterValue(*value))
+            })
+            .count() as u32)
     }
 
     // get extracts a single register value.  It is provided to enable union-ing two
@@ -53,8 +113,19 @@ impl DenseRegisters {
         read_u8_bits(&self.buf, idx, pos, self.settings.reg_width as u8)
     }
 
+    /// set_reg overwrites a register.  Registers only ever grow, so debug builds panic if the value is
+    /// lower than the current one, use `clear` to reset registers.
     pub fn set_reg(&mut self, reg_num: u32, value: u8) {
         let (idx, pos) = calc_position(reg_num, self.settings.reg_width as u8);
+        #[cfg(debug_assertions)]
+        {
+            let current = read_u8_bits(&self.buf, idx, pos, self.settings.reg_width as u8);
+            assert!(
+                value >= current,
+                "register {reg_num} would be lowered from {current} to {value}"
+            );
+        }
+
         write_u8_bits(
             &mut self.buf,
             idx,
@@ -64,7 +135,72 @@ impl DenseRegisters {
         );
     }
 
-    pub fn iter(&self) -> RegisterIter {
+    /// to_sparse returns the registers as SparseRegisters if sparse storage is enabled and no more
+    /// registers are set than the sparse threshold.
+    pub fn to_sparse(&self) -> Option<SparseRegisters> {
+        let threshold = self.settings.sparse_threshold?.max(0) as usize;
+
+        let registers: Vec<(u32, u8)> = self
+            .iter()
+            .filter(|(_, value)| *value != 0)
+            .take(threshold + 1)
+            .collect();
+        if registers.len() > threshold {
+            return None;
+        }
+
+        let mut sparse_registers = SparseRegisters::with_settings(&self.settings);
+        sparse_registers.buf = registers;
+        Some(sparse_registers)
+    }
+
+    /// heap_size returns the number of bytes allocated for the registers.
+    pub fn heap_size(&self) -> usize {
+        let size = self.buf.capacity();
+
+        #[cfg(feature = "touch-counts")]
+        let size = size + self.touches.0.capacity() * size_of::<u32>();
+
+        size
+    }
+
+    /// value_histogram returns the number of registers holding each value, indexed by value.  Values
+    /// past 63, which a 64 bit hash can't produce, are counted as 63.
+    pub fn value_histogram(&self) -> [u32; 64] {
+        let mut histogram = [0; 64];
+        for (_, value) in self.iter() {
+            histogram[(value as usize).min(63)] += 1;
+        }
+
+        histogram
+    }
+
+    /// is_empty returns true if every register is 0.
+    pub fn is_empty(&self) -> bool {
+        self.buf.iter().all(|b| *b == 0)
+    }
+
+    /// check_len verifies that buf holds exactly the registers described by settings.
+    pub(crate) fn check_len(settings: &Settings, buf: &[u8]) -> Result<(), HllError> {
+        let expected = settings.dense_bytes_size();
+
+        if buf.len() < expected {
+            return Err(HllError::Truncated {
+                expected,
+                found: buf.len(),
+            });
+        }
+        if buf.len() > expected {
+            return Err(HllError::StorageLength {
+                type_id: 4,
+                len: buf.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn iter(&self) -> RegisterIter<'_> {
         RegisterIter {
             reg_num: 0,
             reg_cnt: 1 << self.settings.log_2m,
@@ -74,7 +210,8 @@ impl DenseRegisters {
 }
 
 impl Registers for DenseRegisters {
-    fn set_if_greater(&mut self, reg_num: u32, value: u8) {
+    fn set_if_greater(&mut self, reg_num: RegisterIndex, value: RegisterValue) -> bool {
+        let (reg_num, value) = (reg_num.0, value.0);
         let (idx, pos) = calc_position(reg_num, self.settings.reg_width as u8);
         let register = read_u8_bits(&self.buf, idx, pos, self.settings.reg_width as u8);
 
@@ -86,24 +223,19 @@ impl Registers for DenseRegisters {
                 value,
                 self.settings.reg_width as u8,
             );
+            return true;
         }
+
+        false
     }
 
     fn indicator(&self) -> (f64, u32) {
-        let mut sum: f64 = 0.0;
-        let mut number_of_zeros = 0;
-        let num_reg = 1 << self.settings.log_2m;
-
-        for i in 0..num_reg {
-            let value = self.get(i);
-
-            sum += 1.0 / ((1_u64 << value) as f64);
-            if value == 0 {
-                number_of_zeros += 1;
-            }
-        }
+        indicator(&self.settings, &self.buf)
+    }
 
-        (sum, number_of_zeros)
+    #[cfg(feature = "touch-counts")]
+    fn record_touch(&mut self, reg_num: RegisterIndex) {
+        self.touches.0[reg_num.0 as usize] += 1;
     }
 
     fn log_2m(&self) -> u32 {
@@ -125,23 +257,167 @@ impl Storage for DenseRegisters {
     }
 
     fn to_bytes(&self, buf: &mut [u8]) {
+        // registers are packed most significant bit first, the same as the java and postgres
+        // implementations.  There are at least 16 registers so m * reg_width is always a whole
+        // number of bytes and there is no partial trailing byte to pad.
         assert!(buf.len() >= self.buf.len());
         buf[..self.buf.len()].copy_from_slice(&self.buf);
     }
 
-    fn from_bytes(settings: &Settings, buf: &[u8]) -> Self {
+    fn from_bytes(settings: &Settings, buf: &[u8]) -> Result<Self, HllError> {
+        Self::check_len(settings, buf)?;
+
         let mut res = Self::with_settings(settings);
-        assert!(res.buf.len() >= buf.len());
-        res.buf[..buf.len()].copy_from_slice(buf);
+        res.buf.copy_from_slice(buf);
 
-        res
+        // every packed value fits the register width, but wide registers can hold values no hash
+        // produces
+        let max_value = settings.max_hashed_register_value();
+        if max_value < settings.max_register_value()
+            && let Some((_, value)) = res.iter().find(|(_, value)| *value > max_value)
+        {
+            return Err(HllError::RegisterValue(value));
+        }
+
+        Ok(res)
     }
 
     fn clear(&mut self) {
-        for i in 0..self.buf.len() {
-            self.buf[i] = 0;
+        self.buf.fill(0);
+
+        #[cfg(feature = "touch-counts")]
+        self.touches.0.fill(0);
+    }
+}
+
+/// DenseRegistersRef is a read only view of serialized dense registers.  It reads the registers in
+/// place, so the cardinality of serialized Hlls can be computed without copying them.
+#[derive(Debug, Clone, Copy)]
+pub struct DenseRegistersRef<'a> {
+    pub settings: Settings,
+    buf: &'a [u8],
+}
+
+impl<'a> DenseRegistersRef<'a> {
+    /// new wraps the dense storage of a serialized Hll, which must hold exactly the registers
+    /// described by settings.
+    pub fn new(settings: &Settings, buf: &'a [u8]) -> Result<Self, HllError> {
+        DenseRegisters::check_len(settings, buf)?;
+
+        Ok(Self {
+            settings: *settings,
+            buf,
+        })
+    }
+
+    pub fn get(&self, reg_num: u32) -> u8 {
+        let (idx, pos) = calc_position(reg_num, self.settings.reg_width as u8);
+        read_u8_bits(self.buf, idx, pos, self.settings.reg_width as u8)
+    }
+
+    /// indicator returns the sum of 2^-register over every register and the number of zero
+    /// registers.
+    pub fn indicator(&self) -> (f64, u32) {
+        indicator(&self.settings, self.buf)
+    }
+}
+
+/// indicator computes the indicator function over a dense register buffer.  It is shared with
+/// `DenseRegistersRef` so serialized registers can be scanned without copying them.  buf
+/// must hold every register.
+pub(crate) fn indicator(settings: &Settings, buf: &[u8]) -> (f64, u32) {
+    #[cfg(feature = "packed-indicator")]
+    match settings.reg_width {
+        5 => return indicator_packed::<5>(settings, buf),
+        6 => return indicator_packed::<6>(settings, buf),
+        _ => {}
+    }
+
+    indicator_scalar(settings, buf)
+}
+
+/// indicator_packed is the indicator for the common register widths.  Eight registers always fill
+/// exactly REG_WIDTH bytes, so each group of bytes is loaded into a single word and its registers
+/// are unpacked with shifts instead of being read one at a time.  Registers are still summed in
+/// order so the result is identical to `indicator_scalar`.
+#[cfg(feature = "packed-indicator")]
+pub(crate) fn indicator_packed<const REG_WIDTH: usize>(
+    settings: &Settings,
+    buf: &[u8],
+) -> (f64, u32) {
+    let mut sum: f64 = 0.0;
+    let mut number_of_zeros = 0;
+    let mask = (1_u64 << REG_WIDTH) - 1;
+    let len = (1_usize << settings.log_2m) * REG_WIDTH / 8;
+
+    for bytes in buf[..len].chunks_exact(REG_WIDTH) {
+        let word = bytes.iter().fold(0_u64, |word, b| (word << 8) | *b as u64);
+
+        for i in (0..8).rev() {
+            let value = ((word >> (i * REG_WIDTH)) & mask) as u8;
+
+            sum += inverse_pow2(value);
+            number_of_zeros += (value == 0) as u32;
+        }
+    }
+
+    (sum, number_of_zeros)
+}
+
+/// union_packed takes the max of the registers in buf and other, which must share a register width,
+/// and returns the number of registers that were raised.  Eight registers always fill exactly
+/// reg_width bytes, so each group is loaded into a single word and all eight are compared at once
+/// with the packed unsigned comparison from Hacker's Delight (section 2-18).
+pub(crate) fn union_packed(reg_width: usize, buf: &mut [u8], other: &[u8]) -> u32 {
+    let register_mask = (1_u64 << reg_width) - 1;
+    let low_bits = (0..8).fold(0_u64, |bits, i| bits | (1 << (i * reg_width)));
+    let high_bits = low_bits << (reg_width - 1);
+
+    let mut raised = 0;
+    for (bytes, other_bytes) in buf
+        .chunks_exact_mut(reg_width)
+        .zip(other.chunks_exact(reg_width))
+    {
+        let a = bytes.iter().fold(0_u64, |word, b| (word << 8) | *b as u64);
+        let b = other_bytes
+            .iter()
+            .fold(0_u64, |word, b| (word << 8) | *b as u64);
+
+        // setting the high bit of each register in a and clearing it in b keeps the subtraction
+        // from borrowing across registers, the high bit of the difference is then set where the
+        // low bits of a are >= those of b
+        let low_ge = (a | high_bits) - (b & !high_bits);
+        let ge = ((a & !b) | (!(a ^ b) & low_ge)) & high_bits;
+
+        let keep = (ge >> (reg_width - 1)) * register_mask;
+        let word = (a & keep) | (b & !keep);
+        raised += (high_bits & !ge).count_ones();
+
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (word >> (8 * (reg_width - 1 - i))) as u8;
         }
     }
+
+    raised
+}
+
+pub(crate) fn indicator_scalar(settings: &Settings, buf: &[u8]) -> (f64, u32) {
+    let mut sum: f64 = 0.0;
+    let mut number_of_zeros = 0;
+    let num_reg: u32 = 1 << settings.log_2m;
+    let reg_width = settings.reg_width as u8;
+
+    for i in 0..num_reg {
+        let (idx, pos) = calc_position(i, reg_width);
+        let value = read_u8_bits(buf, idx, pos, reg_width);
+
+        sum += inverse_pow2(value);
+        if value == 0 {
+            number_of_zeros += 1;
+        }
+    }
+
+    (sum, number_of_zeros)
 }
 
 pub struct RegisterIter<'a> {
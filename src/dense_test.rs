@@ -1,6 +1,6 @@
 use crate::{
-    Hll, Registers, Settings, dense::DenseRegisters, sparse_test::construct_hll_value,
-    utils::divide_by_8_round_up,
+    Hll, HllError, RegisterIndex, RegisterValue, Registers, Settings, SettingsError,
+    dense::DenseRegisters, sparse_test::construct_hll_value, utils::divide_by_8_round_up,
 };
 
 fn dense_test_settings() -> Settings {
@@ -9,30 +9,54 @@ fn dense_test_settings() -> Settings {
 
 #[test]
 fn test_clear_dense() {
-    let mut hll = Hll::Dense(DenseRegisters::with_settings(
-        &Settings::new(4, 5, 0, false).unwrap(),
-    ));
+    for log_2m in [4, 16] {
+        let mut hll = Hll::Dense(DenseRegisters::with_settings(
+            &Settings::new(log_2m, 5, 0, false).unwrap(),
+        ));
+
+        for i in 0..(10 << log_2m) {
+            hll.add_raw(i as u64);
+        }
 
-    for i in 0..100 {
-        hll.add_raw(i as u64);
+        assert!(hll.cardinality() > 0);
+        let mut cleared = hll.clone();
+        cleared.clear();
+        assert_eq!(Hll::Empty(*hll.settings()), cleared);
+        assert_eq!(0, cleared.cardinality());
+
+        hll.clear_in_place();
+        assert!(matches!(hll, Hll::Dense(_)));
+        assert_eq!(0, hll.cardinality());
     }
+}
+
+#[test]
+fn test_clear_to_bytes() {
+    let settings = dense_test_settings();
+    let mut hll = Hll::new(settings);
+    (0..1000).for_each(|i| hll.add(&i));
+    assert!(matches!(hll, Hll::Dense(_)));
 
-    assert!(hll.cardinality() > 0);
     hll.clear();
-    assert_eq!(0, hll.cardinality());
+    assert_eq!(vec![0x11, 0x8b, 0x00], hll.to_bytes());
+    assert_eq!(Hll::new(settings).to_bytes(), hll.to_bytes());
 }
 
 #[test]
 fn test_to_from_bytes_dense() {
     let settings = dense_test_settings();
-    let expected_byte_count = 3 + divide_by_8_round_up(settings.reg_width * (1 << settings.log_2m));
+    let expected_byte_count = settings.dense_serialized_len();
+    assert_eq!(
+        3 + divide_by_8_round_up(settings.reg_width as usize * (1 << settings.log_2m)),
+        expected_byte_count
+    );
 
     // Test empty element
     {
         let hll = Hll::Dense(DenseRegisters::with_settings(&settings));
         let bytes = hll.to_bytes();
 
-        assert_eq!(expected_byte_count, bytes.len() as u32);
+        assert_eq!(expected_byte_count, bytes.len());
 
         let in_hll = Hll::from_bytes(&bytes).unwrap();
         assert_elements_equal_dense(&hll, &in_hll);
@@ -46,7 +70,7 @@ fn test_to_from_bytes_dense() {
         }
         let bytes = hll.to_bytes();
 
-        assert_eq!(expected_byte_count, bytes.len() as u32);
+        assert_eq!(expected_byte_count, bytes.len());
 
         let in_hll = Hll::from_bytes(&bytes).unwrap();
         assert_elements_equal_dense(&hll, &in_hll);
@@ -60,7 +84,7 @@ fn test_to_from_bytes_dense() {
         }
         let bytes = hll.to_bytes();
 
-        assert_eq!(expected_byte_count, bytes.len() as u32);
+        assert_eq!(expected_byte_count, bytes.len());
 
         let in_hll = Hll::from_bytes(&bytes).unwrap();
         assert_elements_equal_dense(&hll, &in_hll);
@@ -78,12 +102,70 @@ fn test_to_from_bytes_dense_trailing() {
     }
 
     let bytes = hll.to_bytes();
-    assert!(bytes.len() % 8 != 0);
+    assert!(!bytes.len().is_multiple_of(8));
 
     let hll2 = Hll::from_bytes(&bytes).unwrap();
     assert_eq!(hll, hll2);
 }
 
+#[test]
+fn test_java_compatibility_dense() {
+    use crate::Storage;
+
+    // line 2047 of log2m-10_regwidth-4/v1_cumulative_add_cardinality_correction, written by the
+    // java implementation: every register is 7 except the last two, which share the final byte
+    let java_hex = format!("146A49{}10", "7".repeat(1022));
+    let java_bytes = hex::decode(&java_hex).unwrap();
+
+    let settings = Settings::new(10, 4, 256, true).unwrap();
+    let mut registers = DenseRegisters::with_settings(&settings);
+    for i in 0..1022 {
+        registers.set_reg(i, 7);
+    }
+    registers.set_reg(1022, 1);
+
+    let hll = Hll::Dense(registers.clone());
+    assert_eq!(java_hex, hex::encode_upper(hll.to_bytes()));
+
+    let Hll::Dense(from_java) = Hll::from_bytes(&java_bytes).unwrap() else {
+        panic!("expected dense storage");
+    };
+    assert_eq!(1, from_java.get(1022));
+    assert_eq!(0, from_java.get(1023));
+    assert!(registers.iter().eq(from_java.iter()));
+    assert_eq!(registers.bytes_size(), java_bytes.len() - 3);
+}
+
+#[test]
+fn test_from_bytes_register_range() {
+    use crate::Storage;
+
+    // 64 - 11 hash bits are left for the register values
+    let settings = Settings::new(11, 6, 0, false).unwrap();
+    let mut registers = DenseRegisters::with_settings(&settings);
+    registers.set_reg(7, 53);
+    let mut buf = vec![0; registers.bytes_size()];
+    registers.to_bytes(&mut buf);
+    assert_eq!(
+        registers,
+        DenseRegisters::from_bytes(&settings, &buf).unwrap()
+    );
+
+    for value in [54, 63] {
+        registers.set_reg(7, value);
+        registers.to_bytes(&mut buf);
+        assert!(matches!(
+            DenseRegisters::from_bytes(&settings, &buf),
+            Err(HllError::RegisterValue(v)) if v == value
+        ));
+    }
+
+    // every value fits 5 bit registers
+    let settings = dense_test_settings();
+    let buf = vec![0xff; settings.dense_bytes_size()];
+    assert!(DenseRegisters::from_bytes(&settings, &buf).is_ok());
+}
+
 #[test]
 fn test_dense_registers() {
     let tests = vec![
@@ -170,7 +252,7 @@ fn test_dense_get() {
 
     let mut hll = DenseRegisters::with_settings(&settings);
     for i in 0..(1 << settings.log_2m) {
-        hll.set_if_greater(i, (i & (0x80 - 1)) as u8);
+        hll.set_if_greater(RegisterIndex(i), RegisterValue((i & (0x80 - 1)) as u8));
     }
 
     // let t = -4611686018427387904_i64;
@@ -190,3 +272,215 @@ fn assert_elements_equal_dense(hll1: &Hll, hll2: &Hll) {
 fn assert_dense(hll: &Hll) -> bool {
     matches!(hll, Hll::Dense(_))
 }
+
+#[cfg(feature = "touch-counts")]
+#[test]
+fn test_register_touch_counts() {
+    use rand::prelude::*;
+    use rand::rngs::StdRng;
+
+    let settings = Settings::new(8, 5, 0, false).unwrap();
+    let mut hll = Hll::new(settings);
+    assert_eq!(None, hll.register_touch_counts());
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let per_register = 1000;
+    for _ in 0..(per_register << settings.log_2m) {
+        hll.add_raw(rng.random());
+    }
+
+    let counts = hll.register_touch_counts().unwrap();
+    assert_eq!(1 << settings.log_2m, counts.len());
+    for (reg_num, count) in counts.iter().enumerate() {
+        assert!(
+            (per_register * 8 / 10..=per_register * 12 / 10).contains(count),
+            "register {} touched {} times",
+            reg_num,
+            count
+        );
+    }
+}
+
+#[test]
+fn test_union_dense_mismatch() {
+    let mut hll = Hll::Dense(DenseRegisters::with_settings(&dense_test_settings()));
+
+    for settings in [
+        Settings::new(12, 5, 0, false).unwrap(),
+        Settings::new(11, 6, 0, false).unwrap(),
+    ] {
+        let mut other = Hll::new(settings);
+        for i in 0..(1 << settings.log_2m) {
+            other.add_raw(construct_hll_value(settings.log_2m, i, 1));
+        }
+
+        assert!(matches!(
+            hll.union(false, &other),
+            Err(HllError::Settings(SettingsError::MisMatch))
+        ));
+    }
+
+    assert_eq!(0, hll.cardinality());
+}
+
+#[test]
+fn test_indicator_table() {
+    let settings = Settings::new(14, 6, 0, false).unwrap();
+    let mut registers = DenseRegisters::with_settings(&settings);
+    for i in 0..(1 << settings.log_2m) {
+        if i % 5 != 0 {
+            registers.set_reg(i, (i % 64) as u8);
+        }
+    }
+
+    // the indicator computed with a division per register
+    let (sum, zeros) = registers.iter().fold((0.0, 0), |(sum, zeros), (_, value)| {
+        (
+            sum + 1.0 / ((1_u64 << value) as f64),
+            zeros + (value == 0) as u32,
+        )
+    });
+
+    assert_eq!((sum, zeros), registers.indicator());
+}
+
+#[cfg(feature = "packed-indicator")]
+#[test]
+fn test_indicator_packed() {
+    use crate::dense::{indicator_packed, indicator_scalar};
+    use rand::prelude::*;
+    use rand::rngs::StdRng;
+
+    let mut rng = StdRng::seed_from_u64(0);
+    for log_2m in [4, 11, 16] {
+        for reg_width in [5, 6] {
+            let settings = Settings::new(log_2m, reg_width, 0, false).unwrap();
+            let mut buf = vec![0; settings.dense_bytes_size()];
+
+            for _ in 0..10 {
+                rng.fill(&mut buf[..]);
+
+                let expected = indicator_scalar(&settings, &buf);
+                let packed = match reg_width {
+                    5 => indicator_packed::<5>(&settings, &buf),
+                    _ => indicator_packed::<6>(&settings, &buf),
+                };
+                assert_eq!(expected.0.to_bits(), packed.0.to_bits());
+                assert_eq!(expected.1, packed.1);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "touch-counts")]
+#[test]
+fn test_register_touch_counts_from_sparse() {
+    let settings = Settings::new(8, 5, 0, true).unwrap();
+    let mut hll = Hll::new(settings);
+
+    // every register is touched 4 times, the first ones while the Hll is still sparse
+    let m = 1 << settings.log_2m;
+    for i in 0..4 * m {
+        hll.add_raw(construct_hll_value(settings.log_2m, i % m, 1));
+        if i == 0 {
+            assert!(matches!(hll, Hll::Sparse(_)));
+        }
+    }
+
+    assert!(matches!(hll, Hll::Dense(_)));
+    assert_eq!(vec![4; m as usize], hll.register_touch_counts().unwrap());
+}
+
+#[test]
+fn test_union_packed() {
+    use rand::prelude::*;
+    use rand::rngs::StdRng;
+
+    let mut rng = StdRng::seed_from_u64(0);
+    for log_2m in [4, 11] {
+        for reg_width in 1..=8 {
+            let settings = Settings::new(log_2m, reg_width, 0, false).unwrap();
+            // every bit pattern a register can hold, not just the values a hash can produce
+            let mut random_registers = || {
+                let mut registers = DenseRegisters::with_settings(&settings);
+                for i in 0..settings.registers() {
+                    registers.set_reg(i, rng.random_range(0..=settings.max_register_value()));
+                }
+                registers
+            };
+
+            for _ in 0..10 {
+                let mut registers = random_registers();
+                let other = random_registers();
+
+                let mut expected = registers.clone();
+                let expected_raised = other
+                    .iter()
+                    .filter(|(reg_num, value)| {
+                        expected.set_if_greater(RegisterIndex(*reg_num), RegisterValue(*value))
+                    })
+                    .count() as u32;
+
+                assert_eq!(expected_raised, registers.union_dense(&other).unwrap());
+                assert_eq!(expected, registers);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_dense_registers_ref() {
+    use crate::{DenseRegistersRef, Storage};
+
+    for reg_width in [1, 5, 6, 8] {
+        let settings = Settings::new(11, reg_width, 0, false).unwrap();
+        let mut registers = DenseRegisters::with_settings(&settings);
+        for i in 0..(1 << settings.log_2m) {
+            registers.set_if_greater(
+                RegisterIndex(i),
+                RegisterValue((i % (1 << reg_width)) as u8),
+            );
+        }
+
+        let mut buf = vec![0; registers.bytes_size()];
+        registers.to_bytes(&mut buf);
+
+        let view = DenseRegistersRef::new(&settings, &buf).unwrap();
+        assert!(
+            registers
+                .iter()
+                .all(|(reg_num, value)| view.get(reg_num) == value)
+        );
+        assert_eq!(registers.indicator(), view.indicator());
+
+        assert!(matches!(
+            DenseRegistersRef::new(&settings, &buf[1..]),
+            Err(HllError::Truncated { .. })
+        ));
+    }
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "register 3 would be lowered from 5 to 2")]
+fn test_set_reg_regression() {
+    let mut registers = DenseRegisters::with_settings(&dense_test_settings());
+    registers.set_reg(3, 5);
+    registers.set_reg(3, 5);
+    registers.set_reg(3, 2);
+}
+
+#[test]
+fn test_heap_size() {
+    for (log_2m, reg_width) in [(4, 1), (11, 5), (16, 6)] {
+        let settings = Settings::new(log_2m, reg_width, 0, false).unwrap();
+        let mut hll = Hll::new(settings);
+        hll.add("apple");
+
+        let mut expected = divide_by_8_round_up((reg_width << log_2m) as usize);
+        if cfg!(feature = "touch-counts") {
+            expected += 4 << log_2m;
+        }
+        assert_eq!(expected, hll.heap_size());
+    }
+}
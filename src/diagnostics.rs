@@ -0,0 +1,293 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::Hll;
+use crate::math;
+use crate::utils::inverse_pow2;
+
+impl Hll {
+    /// register_value_distribution returns the fraction of registers holding each register value,
+    /// indexed by value.  This is a hash-quality diagnostic, see `distribution_chi_square`.
+    pub fn register_value_distribution(&self) -> Vec<f64> {
+        let counts = self.register_value_counts();
+        let m = (1_u64 << self.settings().log_2m) as f64;
+
+        counts.iter().map(|count| *count as f64 / m).collect()
+    }
+
+    /// distribution_chi_square compares the register values against the distribution expected for
+    /// the estimated cardinality and returns Pearson's chi-square statistic.  Register values of a
+    /// well distributed hash follow a known geometric-like distribution, so a statistic far above
+    /// the number of populated value buckets (roughly 10-20) indicates a biased hash.
+    ///
+    /// The expectation uses the poissonized model where each register sees Poisson(n/m) values, so
+    /// P(M <= k) = exp(-(n/m) / 2^k).  Buckets with an expected count below 5 are merged into their
+    /// neighbor as is usual for the test.
+    pub fn distribution_chi_square(&self) -> f64 {
+        let counts = self.register_value_counts();
+        let m = (1_u64 << self.settings().log_2m) as f64;
+        let lambda = self.cardinality() as f64 / m;
+        let max_value = counts.len() - 1;
+
+        // P(M <= k) for the poissonized model, with the largest value absorbing the tail
+        let cdf = |k: usize| -> f64 {
+            if k >= max_value {
+                return 1.0;
+            }
+
+            math::exp(-lambda * inverse_pow2(k as u8))
+        };
+
+        let mut buckets: Vec<(f64, f64)> = Vec::new();
+        let (mut observed, mut expected) = (0.0, 0.0);
+        for (k, count) in counts.iter().enumerate() {
+            let below = if k == 0 { 0.0 } else { cdf(k - 1) };
+            observed += *count as f64;
+            expected += (cdf(k) - below) * m;
+
+            if expected >= 5.0 {
+                buckets.push((observed, expected));
+                (observed, expected) = (0.0, 0.0);
+            }
+        }
+
+        match buckets.last_mut() {
+            Some(last) => {
+                last.0 += observed;
+                last.1 += expected;
+            }
+            None => buckets.push((observed, expected)),
+        }
+
+        buckets
+            .iter()
+            .filter(|(_, expected)| *expected > 0.0)
+            .map(|(observed, expected)| (observed - expected) * (observed - expected) / expected)
+            .sum()
+    }
+
+    /// register_histogram returns the number of registers holding each value, indexed by value.
+    /// Explicit storage is materialized into registers first.  Values past the largest one a
+    /// register can hold are always 0.
+    ///
+    /// A histogram concentrated at the largest value, (1 << reg_width) - 1, means reg_width is too
+    /// small for the cardinality, see `saturated_register_count`.
+    pub fn register_histogram(&self) -> [u32; 64] {
+        if let Hll::Dense(dense_registers) = self {
+            return dense_registers.value_histogram();
+        }
+
+        let mut histogram = [0; 64];
+        for (value, count) in self.register_value_counts().into_iter().enumerate() {
+            histogram[value] = count as u32;
+        }
+
+        histogram
+    }
+
+    /// saturated_register_count returns the number of registers holding the largest value their
+    /// width allows, (1 << reg_width) - 1.  Saturated registers cap the estimate, so a count that
+    /// is a noticeable fraction of the 2^log_2m registers means reg_width is too small for the
+    /// cardinality and the Hll should be rebuilt with wider registers.
+    pub fn saturated_register_count(&self) -> u32 {
+        let max_value = self.settings().max_register_value();
+
+        self.nonzero_registers()
+            .filter(|(_, value)| *value == max_value)
+            .count() as u32
+    }
+
+    /// nonzero_register_count returns the number of registers that are set.  Explicit storage is
+    /// materialized into registers first and Empty storage has none.  Comparing it against the
+    /// number of registers shows how close Sparse storage is to being worth converting to Dense.
+    pub fn nonzero_register_count(&self) -> u32 {
+        match self {
+            Hll::Empty(_) => 0,
+            Hll::Explicit(explicit_storage) => explicit_storage.into_sparse().len() as u32,
+            Hll::Sparse(sparse_registers) => sparse_registers.len() as u32,
+            Hll::Dense(dense_registers) => dense_registers
+                .iter()
+                .filter(|(_, value)| *value != 0)
+                .count() as u32,
+        }
+    }
+
+    /// register_value_counts returns the number of registers holding each register value, indexed
+    /// by value.  Explicit storage is materialized into registers first.  Values above 63, which no
+    /// hash produces but `DenseRegisters::set_reg` can store in wide registers, are counted as 63.
+    fn register_value_counts(&self) -> Vec<u64> {
+        let settings = self.settings();
+        let m = 1_u64 << settings.log_2m;
+
+        // p(w) can't exceed 63 regardless of the register width
+        let max_value = ((1_u64 << settings.reg_width) - 1).min(63) as usize;
+        let mut counts = vec![0; max_value + 1];
+        let mut count = |value: u8| counts[(value as usize).min(max_value)] += 1;
+
+        match self {
+            Hll::Empty(_) => {}
+            Hll::Explicit(explicit_storage) => {
+                return explicit_storage.as_registers().register_value_counts();
+            }
+            Hll::Sparse(sparse_registers) => {
+                // registers missing from the sparse storage, or stored as 0, are counted below
+                sparse_registers
+                    .iter()
+                    .filter(|(_, value)| *value != 0)
+                    .for_each(|(_, value)| count(*value));
+            }
+            Hll::Dense(dense_registers) => {
+                dense_registers
+                    .iter()
+                    .filter(|(_, value)| *value != 0)
+                    .for_each(|(_, value)| count(value));
+            }
+        }
+
+        counts[0] = m - counts.iter().sum::<u64>();
+        counts
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::prelude::*;
+    use rand::rngs::StdRng;
+
+    use crate::{Hll, Settings};
+
+    #[test]
+    fn register_value_distribution() {
+        let settings = Settings::new(11, 5, 0, false).unwrap();
+        let hll = Hll::new(settings);
+        let distribution = hll.register_value_distribution();
+
+        assert_eq!(32, distribution.len());
+        assert_eq!(1.0, distribution[0]);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut hll = Hll::new(settings);
+        for _ in 0..100_000 {
+            hll.add_raw(rng.random());
+        }
+
+        let distribution = hll.register_value_distribution();
+        assert!((distribution.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert_eq!(0.0, distribution[0]);
+    }
+
+    #[test]
+    fn register_value_counts_edge_cases() {
+        use crate::{DenseRegisters, RegisterIndex, RegisterValue, Registers, SparseRegisters};
+
+        // a sparse entry holding 0 is still a single zero register
+        let settings = Settings::new(4, 5, 0, true).unwrap();
+        let mut sparse_registers = SparseRegisters::with_settings(&settings);
+        sparse_registers.set_if_greater(RegisterIndex(3), RegisterValue(0));
+        sparse_registers.set_if_greater(RegisterIndex(5), RegisterValue(2));
+
+        let counts = Hll::Sparse(sparse_registers).register_value_counts();
+        assert_eq!(15, counts[0]);
+        assert_eq!(1, counts[2]);
+        assert_eq!(16, counts.iter().sum::<u64>());
+
+        // values that don't fit the histogram are counted in its last bucket
+        let settings = Settings::new(4, 8, 0, false).unwrap();
+        let mut registers = DenseRegisters::with_settings(&settings);
+        registers.set_reg(0, 200);
+        registers.set_reg(1, 63);
+        let counts = Hll::Dense(registers).register_value_counts();
+        assert_eq!(64, counts.len());
+        assert_eq!(14, counts[0]);
+        assert_eq!(2, counts[63]);
+    }
+
+    #[test]
+    fn register_histogram() {
+        let settings = Settings::new(4, 5, 0, true).unwrap();
+        let mut hll = Hll::new(settings);
+        let mut expected = [0; 64];
+        expected[0] = 16;
+        assert_eq!(expected, hll.register_histogram());
+
+        // register i gets value i + 1
+        for i in 0..8 {
+            hll.add_raw((1 << (settings.log_2m + i)) | i as u64);
+        }
+        assert!(matches!(hll, Hll::Sparse(_)));
+
+        expected[0] = 8;
+        expected[1..=8].fill(1);
+        assert_eq!(expected, hll.register_histogram());
+
+        let dense = hll.clone().into_dense();
+        assert_eq!(expected, dense.register_histogram());
+        if let Hll::Dense(dense_registers) = &dense {
+            assert_eq!(expected, dense_registers.value_histogram());
+        }
+    }
+
+    #[test]
+    fn nonzero_register_count() {
+        let settings = Settings::new(11, 5, 0, true).unwrap();
+        let mut hll = Hll::new(settings);
+        assert_eq!(0, hll.nonzero_register_count());
+
+        for i in 0..5 {
+            hll.add_raw((1 << 20) | i);
+        }
+        assert!(matches!(hll, Hll::Sparse(_)));
+        assert_eq!(5, hll.nonzero_register_count());
+        assert_eq!(5, hll.clone().into_dense().nonzero_register_count());
+
+        let mut explicit = Hll::new(Settings::new(11, 5, -1, true).unwrap());
+        for i in 0..5 {
+            explicit.add_raw((1 << 20) | i);
+        }
+        assert!(matches!(explicit, Hll::Explicit(_)));
+        assert_eq!(5, explicit.nonzero_register_count());
+    }
+
+    #[test]
+    fn saturated_register_count() {
+        let settings = Settings::new(11, 5, 0, true).unwrap();
+        let mut hll = Hll::new(settings);
+        assert_eq!(0, hll.saturated_register_count());
+
+        // the top bit alone gives far more trailing zeros than 5 bit registers can hold
+        for i in 0..100 {
+            hll.add_raw((1 << 63) | i);
+            hll.add_raw((1 << 12) | (i + 100));
+        }
+        assert!(matches!(hll, Hll::Sparse(_)));
+        assert_eq!(100, hll.saturated_register_count());
+
+        for i in 0..(1 << settings.log_2m) {
+            hll.add_raw((1 << 63) | i);
+        }
+        assert!(matches!(hll, Hll::Dense(_)));
+        assert_eq!(1 << settings.log_2m, hll.saturated_register_count());
+    }
+
+    #[test]
+    fn distribution_chi_square() {
+        let settings = Settings::new(11, 5, 0, false).unwrap();
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut uniform = Hll::new(settings);
+        for _ in 0..100_000 {
+            uniform.add_raw(rng.random());
+        }
+
+        // a hash that only ever sets the same bit pattern puts every register at the same value
+        let mut degenerate = Hll::new(settings);
+        for i in 0..(1 << settings.log_2m) {
+            degenerate.add_raw((0b10000 << settings.log_2m) | i);
+        }
+
+        let uniform = uniform.distribution_chi_square();
+        let degenerate = degenerate.distribution_chi_square();
+        assert!(uniform < 30.0, "uniform chi-square {}", uniform);
+        assert!(degenerate > 1000.0, "degenerate chi-square {}", degenerate);
+    }
+}
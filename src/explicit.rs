@@ -1,90 +1,309 @@
-use std::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
 
-use crate::{Hll, Storage, dense::DenseRegisters, settings::Settings, sparse::SparseRegisters};
+use crate::{
+    Hll, HllError, RegisterIndex, RegisterValue, Registers, Storage, dense::DenseRegisters,
+    settings::Settings, sparse::SparseRegisters,
+};
 
-#[derive(Debug, Clone, PartialEq)]
+/// ExplicitOrder is the order explicit values are serialized in, see `Hll::to_bytes_ordered`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ExplicitOrder {
+    /// ascending as signed 64 bit integers.  This is what `to_bytes` writes and matches the
+    /// PostgreSQL and Java implementations byte for byte.
+    #[default]
+    Signed,
+    /// ascending as unsigned 64 bit integers, for implementations that store hashes unsigned.
+    Unsigned,
+}
+
+// EMPTY marks an unused slot of the hash table, so a value of 0 is tracked outside of it
+const EMPTY: i64 = 0;
+
+// the table is reserved in full on the first insert up to this many slots, larger thresholds
+// grow towards their size as values are added
+const INITIAL_SLOTS: usize = 1024;
+
+/// ExplicitStorage holds the distinct values in an open addressing hash table sized from the
+/// explicit threshold.  Inserting and deduplicating is a single probe, where a sorted Vec has to
+/// shift every larger value and a BTreeSet allocates nodes as it fills.  The values are sorted as
+/// i64, the order the storage spec serializes them in, when they are iterated or serialized.
+#[derive(Clone)]
 pub struct ExplicitStorage {
     pub settings: Settings,
-    buf: BTreeSet<i64>,
+    // a power of two number of slots, at most half of them in use
+    slots: Vec<i64>,
+    has_empty: bool,
+    len: usize,
 }
 
 impl ExplicitStorage {
     pub fn with_settings(settings: &Settings) -> Self {
         Self {
             settings: *settings,
-            buf: BTreeSet::new(),
+            slots: Vec::new(),
+            has_empty: false,
+            len: 0,
         }
     }
 
     pub fn clone_with_settings(&self, settings: &Settings) -> Self {
         Self {
             settings: *settings,
-            buf: self.buf.clone(),
+            ..self.clone()
         }
     }
 
     pub fn as_registers(&self) -> Hll {
-        let mut storage = match self.settings.sparse_threshold {
-            Some(_) => Hll::Sparse(SparseRegisters::with_settings(&self.settings)),
-            None => Hll::Dense(DenseRegisters::with_settings(&self.settings)),
-        };
+        if self.settings.sparse_threshold.is_none() {
+            let mut storage = Hll::Dense(DenseRegisters::with_settings(&self.settings));
+            for value in self.values() {
+                storage.add_raw(value as u64);
+            }
+
+            return storage;
+        }
+
+        let sparse_registers = self.into_sparse();
+        match sparse_registers.is_full() {
+            true => Hll::Dense(sparse_registers.to_dense(None)),
+            false => Hll::Sparse(sparse_registers),
+        }
+    }
+
+    /// into_sparse converts the values into sparse registers in one pass, regardless of the sparse
+    /// threshold.  Check `SparseRegisters::is_full` before using the result as Sparse storage.
+    pub fn into_sparse(&self) -> SparseRegisters {
+        let mut sparse_registers = SparseRegisters::with_settings(&self.settings);
+
+        let mut registers: Vec<(RegisterIndex, RegisterValue)> = self
+            .iter()
+            .filter_map(|value| sparse_registers.register_for(value))
+            .collect();
+        registers.sort_unstable();
 
-        for value in self.buf.iter() {
-            storage.add_raw(*value as u64);
+        // registers are sorted so every set appends to the end of the sparse registers
+        for (reg_num, value) in registers {
+            sparse_registers.set_if_greater(reg_num, value);
         }
 
-        storage
+        sparse_registers
     }
 
     pub fn set(&mut self, value: u64) {
-        self.buf.insert(value as i64);
+        self.insert(value as i64);
+    }
+
+    /// insert adds value if it isn't already held and returns whether it was added.
+    fn insert(&mut self, value: i64) -> bool {
+        if value == EMPTY {
+            let added = !self.has_empty;
+            self.has_empty = true;
+            self.len += added as usize;
+            return added;
+        }
+
+        if (self.len + 1) * 2 > self.slots.len() {
+            self.grow();
+        }
+
+        let slot = self.probe(value);
+        if self.slots[slot] == value {
+            return false;
+        }
+
+        self.slots[slot] = value;
+        self.len += 1;
+        true
+    }
+
+    /// probe returns the slot holding value, or the empty slot it belongs in.
+    fn probe(&self, value: i64) -> usize {
+        let mask = self.slots.len() - 1;
+        let shift = u64::BITS - self.slots.len().trailing_zeros();
+
+        // the values are usually hashes already, but mix them in case they aren't
+        let mut slot = ((value as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> shift) as usize;
+        while self.slots[slot] != EMPTY && self.slots[slot] != value {
+            slot = (slot + 1) & mask;
+        }
+
+        slot
+    }
+
+    fn contains(&self, value: i64) -> bool {
+        match value {
+            EMPTY => self.has_empty,
+            _ => !self.slots.is_empty() && self.slots[self.probe(value)] == value,
+        }
+    }
+
+    /// grow doubles the table, or on the first insert reserves it for the explicit threshold.
+    fn grow(&mut self) {
+        let slots = match self.slots.len() {
+            0 => {
+                // the storage upgrades once it holds one value more than the threshold
+                let threshold = self.settings.explicit_threshold() as usize + 1;
+                (threshold * 2).next_power_of_two().min(INITIAL_SLOTS)
+            }
+            len => len * 2,
+        };
+
+        self.rehash(slots);
+    }
+
+    fn rehash(&mut self, slots: usize) {
+        let old = core::mem::replace(&mut self.slots, vec![EMPTY; slots]);
+        for value in old.into_iter().filter(|value| *value != EMPTY) {
+            let slot = self.probe(value);
+            self.slots[slot] = value;
+        }
+    }
+
+    /// values returns the values in no particular order.
+    fn values(&self) -> impl Iterator<Item = i64> {
+        self.slots
+            .iter()
+            .copied()
+            .filter(|value| *value != EMPTY)
+            .chain(self.has_empty.then_some(EMPTY))
+    }
+
+    /// sorted returns the values sorted as i64.
+    fn sorted(&self) -> Vec<i64> {
+        let mut values: Vec<i64> = self.values().collect();
+        values.sort_unstable();
+        values
     }
 
     pub fn is_full(&self) -> bool {
-        self.buf.len() as u32 > self.settings.explicit_threshold()
+        self.len as u32 > self.settings.explicit_threshold()
     }
 
-    pub fn union_explicit(&mut self, other: &Self) {
-        self.buf.extend(other.buf.iter());
+    /// union_explicit returns the number of values that were added.  The smaller set is inserted
+    /// into the larger one, so a small Hll absorbing a large one copies the large table instead of
+    /// inserting every one of its values.
+    pub fn union_explicit(&mut self, other: &Self) -> u32 {
+        let len = self.len;
+        if other.len > len {
+            let mut larger = other.clone_with_settings(&self.settings);
+            for value in self.values() {
+                larger.insert(value);
+            }
+            *self = larger;
+        } else {
+            for value in other.values() {
+                self.insert(value);
+            }
+        }
+
+        (self.len - len) as u32
     }
 
+    /// iter returns the values in ascending order as i64.
     pub fn iter(&self) -> impl Iterator<Item = u64> {
-        self.buf.iter().map(|i| *i as u64)
+        self.sorted().into_iter().map(|i| i as u64)
     }
 
     pub fn len(&self) -> u64 {
-        self.buf.len() as u64
+        self.len as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// shrink_to_fit rebuilds the table with the fewest slots that hold the values.
+    pub fn shrink_to_fit(&mut self) {
+        let in_table = self.len - self.has_empty as usize;
+        let slots = match in_table {
+            0 => 0,
+            _ => (in_table * 2).next_power_of_two(),
+        };
+
+        if slots < self.slots.len() {
+            self.rehash(slots);
+            self.slots.shrink_to_fit();
+        }
+    }
+
+    /// heap_size returns the number of bytes allocated for the table.
+    pub fn heap_size(&self) -> usize {
+        self.slots.capacity() * size_of::<i64>()
     }
 }
 
-impl Storage for ExplicitStorage {
-    fn bytes_size(&self) -> usize {
-        size_of::<i64>() * self.buf.len()
+impl PartialEq for ExplicitStorage {
+    fn eq(&self, other: &Self) -> bool {
+        self.settings == other.settings
+            && self.len == other.len
+            && other.values().all(|value| self.contains(value))
     }
+}
 
-    fn to_bytes(&self, buf: &mut [u8]) {
-        for (i, value) in self.buf.iter().enumerate() {
+impl fmt::Debug for ExplicitStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExplicitStorage")
+            .field("settings", &self.settings)
+            .field("values", &self.sorted())
+            .finish()
+    }
+}
+
+impl ExplicitStorage {
+    /// to_bytes_ordered is `Storage::to_bytes` with the values written in the given order.
+    pub fn to_bytes_ordered(&self, order: ExplicitOrder, buf: &mut [u8]) {
+        // unsigned order starts at the non-negative values and continues with the negative values,
+        // which are the large unsigned ones
+        let values = self.sorted();
+        let split = match order {
+            ExplicitOrder::Signed => 0,
+            ExplicitOrder::Unsigned => values.partition_point(|value| *value < 0),
+        };
+        let values = values[split..].iter().chain(values[..split].iter());
+
+        for (i, value) in values.enumerate() {
             let idx = i * size_of::<i64>();
             buf[idx..(idx + size_of::<i64>())].copy_from_slice(&(*value).to_be_bytes());
         }
     }
+}
 
-    fn from_bytes(settings: &Settings, buf: &[u8]) -> Self {
-        let mut res = Self::with_settings(settings);
-        let mut idx = 0;
+impl Storage for ExplicitStorage {
+    fn bytes_size(&self) -> usize {
+        size_of::<i64>() * self.len
+    }
+
+    fn to_bytes(&self, buf: &mut [u8]) {
+        self.to_bytes_ordered(ExplicitOrder::Signed, buf);
+    }
 
-        while idx < buf.len() {
-            let s = &buf[idx..(idx + size_of::<i64>())];
-            let value = i64::from_be_bytes(s.try_into().unwrap());
-            res.buf.insert(value);
+    fn from_bytes(settings: &Settings, buf: &[u8]) -> Result<Self, HllError> {
+        let values = buf.chunks_exact(size_of::<i64>());
+        if !values.remainder().is_empty() {
+            return Err(HllError::StorageLength {
+                type_id: 2,
+                len: buf.len(),
+            });
+        }
 
-            idx += size_of::<i64>();
+        // values written by other implementations aren't necessarily sorted or distinct, but
+        // there are never more of them than the payload holds
+        let mut res = Self::with_settings(settings);
+        if values.len() > 0 {
+            res.rehash((values.len() * 2).next_power_of_two());
+        }
+        for value in values {
+            res.insert(i64::from_be_bytes(value.try_into().unwrap()));
         }
 
-        res
+        Ok(res)
     }
 
     fn clear(&mut self) {
-        self.buf.clear();
+        self.slots.fill(EMPTY);
+        self.has_empty = false;
+        self.len = 0;
     }
 }
@@ -0,0 +1,132 @@
+use alloc::vec::Vec;
+use core::hash::Hasher;
+
+const C1: u64 = 0x87c3_7b91_1142_53d5;
+const C2: u64 = 0x4cf5_ad43_2745_937f;
+
+/// murmur3_64 returns the lower 64 bits of the 128 bit x64 variant of MurmurHash3 with a seed of 0.
+/// This is the hash used by the postgres extension's `hll_hash_*` functions and the one recommended
+/// for the java implementation, so values hashed with it land in the same registers across all of
+/// them.
+pub fn murmur3_64(bytes: &[u8]) -> u64 {
+    let mut h1: u64 = 0;
+    let mut h2: u64 = 0;
+
+    let mut blocks = bytes.chunks_exact(16);
+    for block in blocks.by_ref() {
+        let k1 = u64::from_le_bytes(block[..8].try_into().unwrap());
+        let k2 = u64::from_le_bytes(block[8..].try_into().unwrap());
+
+        h1 ^= mix_k1(k1);
+        h1 = h1.rotate_left(27).wrapping_add(h2);
+        h1 = h1.wrapping_mul(5).wrapping_add(0x52dc_e729);
+
+        h2 ^= mix_k2(k2);
+        h2 = h2.rotate_left(31).wrapping_add(h1);
+        h2 = h2.wrapping_mul(5).wrapping_add(0x3849_5ab5);
+    }
+
+    let tail = blocks.remainder();
+    if tail.len() > 8 {
+        h2 ^= mix_k2(read_le(&tail[8..]));
+    }
+    if !tail.is_empty() {
+        h1 ^= mix_k1(read_le(&tail[..tail.len().min(8)]));
+    }
+
+    h1 ^= bytes.len() as u64;
+    h2 ^= bytes.len() as u64;
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    h1 = fmix(h1);
+    h2 = fmix(h2);
+
+    h1.wrapping_add(h2)
+}
+
+fn mix_k1(k1: u64) -> u64 {
+    k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2)
+}
+
+fn mix_k2(k2: u64) -> u64 {
+    k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1)
+}
+
+/// read_le reads up to 8 bytes as a little endian u64.
+fn read_le(bytes: &[u8]) -> u64 {
+    bytes.iter().rev().fold(0, |acc, b| (acc << 8) | *b as u64)
+}
+
+fn fmix(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^ (k >> 33)
+}
+
+/// `Murmur3Hasher` adapts `murmur3_64` to `core::hash::Hasher`.  Murmur3 is not a streaming hash so
+/// written bytes are buffered until `finish`.  Integers are written little endian so results do
+/// not depend on the platform.
+///
+/// Note that `Hash` implementations may write more than the raw value: `str` appends a 0xff byte
+/// and slices are prefixed with their length.  Integers hash to the same value as the postgres
+/// `hll_hash_*` functions but strings and byte slices do not, use `murmur3_64` directly on the raw
+/// bytes for those.
+#[derive(Clone, Debug, Default)]
+pub struct Murmur3Hasher {
+    buf: Vec<u8>,
+}
+
+impl Hasher for Murmur3Hasher {
+    fn finish(&self) -> u64 {
+        murmur3_64(&self.buf)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write(&i.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::hash::{Hash, Hasher};
+
+    use super::{Murmur3Hasher, murmur3_64};
+
+    #[test]
+    fn reference_values() {
+        assert_eq!(0, murmur3_64(b""));
+        assert_eq!(
+            0xe34b_bc7b_bc07_1b6c,
+            murmur3_64(b"The quick brown fox jumps over the lazy dog")
+        );
+    }
+
+    #[test]
+    fn hasher() {
+        let mut hasher = Murmur3Hasher::default();
+        1234_i64.hash(&mut hasher);
+        assert_eq!(murmur3_64(&1234_i64.to_le_bytes()), hasher.finish());
+    }
+}
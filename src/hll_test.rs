@@ -0,0 +1,1849 @@
+use std::collections::BTreeMap;
+
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+use crate::{
+    DenseRegisters, ExplicitOrder, ExplicitStorage, Hll, HllError, RegisterIndex, RegisterValue,
+    Registers, Settings, SettingsBuilder, SettingsError, SparseRegisters, murmur3_64,
+    sparse_test::construct_hll_value,
+};
+
+#[test]
+fn test_union_bytes() {
+    let settings = Settings::new(11, 5, 0, true).unwrap();
+
+    let mut dense = Hll::new(Settings::new(11, 5, 0, false).unwrap());
+    for i in 0..100 {
+        dense.add_raw(construct_hll_value(settings.log_2m, i * 3, (i % 7) + 1));
+    }
+    assert!(matches!(dense, Hll::Dense(_)));
+
+    let mut sparse = Hll::new(settings);
+    for i in 0..10 {
+        sparse.add_raw(construct_hll_value(settings.log_2m, i * 5, (i % 11) + 1));
+    }
+    assert!(matches!(sparse, Hll::Sparse(_)));
+
+    let mut expected = sparse.clone();
+    expected
+        .union(true, &Hll::from_bytes(&dense.to_bytes()).unwrap())
+        .unwrap();
+
+    sparse.union_bytes(true, &dense.to_bytes()).unwrap();
+    assert_eq!(expected, sparse);
+    assert_eq!(expected.cardinality(), sparse.cardinality());
+}
+
+#[test]
+fn test_from_register_map() {
+    let settings = Settings::new(11, 5, 0, true).unwrap();
+    let regs: BTreeMap<u32, u8> = (0..20).map(|i| (i * 7, ((i % 31) + 1) as u8)).collect();
+
+    let hll = Hll::from_register_map(settings, &regs).unwrap();
+    match &hll {
+        Hll::Sparse(sparse) => {
+            assert_eq!(regs, sparse.iter().copied().collect());
+        }
+        _ => panic!("expected sparse storage"),
+    }
+
+    // too many registers for the sparse representation
+    let regs: BTreeMap<u32, u8> = (0..(1 << settings.log_2m))
+        .map(|i| (i, ((i % 31) + 1) as u8))
+        .collect();
+
+    let hll = Hll::from_register_map(settings, &regs).unwrap();
+    match &hll {
+        Hll::Dense(dense) => {
+            assert_eq!(regs, dense.iter().collect());
+        }
+        _ => panic!("expected dense storage"),
+    }
+
+    let regs = BTreeMap::from([(1 << settings.log_2m, 1)]);
+    assert!(matches!(
+        Hll::from_register_map(settings, &regs),
+        Err(HllError::RegisterIndex(2048))
+    ));
+
+    let regs = BTreeMap::from([(0, 32)]);
+    assert!(matches!(
+        Hll::from_register_map(settings, &regs),
+        Err(HllError::RegisterValue(32))
+    ));
+
+    // 8 bit registers hold up to 255, but the hash only produces up to 64 - log_2m, which is also
+    // all `from_bytes` accepts
+    for sparse_enabled in [true, false] {
+        let settings = Settings::new(16, 8, 0, sparse_enabled).unwrap();
+        let regs = BTreeMap::from([(0, 49)]);
+        assert!(matches!(
+            Hll::from_register_map(settings, &regs),
+            Err(HllError::RegisterValue(49))
+        ));
+
+        let regs: BTreeMap<u32, u8> = (0..100).map(|i| (i * 13, 48)).collect();
+        let hll = Hll::from_register_map(settings, &regs).unwrap();
+        assert_eq!(hll, Hll::from_bytes(&hll.to_bytes()).unwrap());
+    }
+}
+
+/// saturated_hll returns an Hll whose estimate is past u64::MAX.  Every register of log_2m 29 and
+/// reg_width 5 is at 31, which p(w) can produce with the 35 hash bits left above the register
+/// index.  That puts the raw estimator past 2^L = 2^59, where the large range correction saturates
+/// at about 36 * 2^59.  Smaller log_2m or other widths can't get past u64::MAX, so this needs
+/// about 670 MB.
+#[cfg(not(feature = "touch-counts"))]
+fn saturated_hll() -> Hll {
+    use crate::Storage;
+
+    let settings = Settings::new(29, 5, 0, false).unwrap();
+    let buf = vec![0xFF; settings.dense_bytes_size()];
+    Hll::Dense(DenseRegisters::from_bytes(&settings, &buf).unwrap())
+}
+
+#[test]
+fn test_cardinality_checked() {
+    // every register at the largest value p(w) can produce for log_2m 16 keeps the estimate, about
+    // alpha * 2^64, in range
+    let settings = Settings::new(16, 8, 0, false).unwrap();
+    let max_value = 64 - settings.log_2m;
+    let mut hll = Hll::new(settings);
+    for i in 0..(1 << settings.log_2m) {
+        hll.add_raw(construct_hll_value(settings.log_2m, i, max_value));
+    }
+
+    let cardinality = hll.cardinality();
+    assert!(cardinality > u64::MAX / 2 && cardinality < u64::MAX);
+    assert_eq!(Some(cardinality), hll.cardinality_checked());
+
+    let mut hll = Hll::new(settings);
+    for i in 0..100 {
+        hll.add_raw(construct_hll_value(settings.log_2m, i, 1));
+    }
+    assert_eq!(Some(hll.cardinality()), hll.cardinality_checked());
+}
+
+// touch counts would allocate 2^29 u32s
+#[cfg(not(feature = "touch-counts"))]
+#[test]
+#[ignore = "allocates about 670 MB, run with cargo test -- --ignored"]
+fn test_cardinality_saturates() {
+    let hll = saturated_hll();
+
+    assert_eq!(u64::MAX, hll.cardinality());
+    assert_eq!(None, hll.cardinality_checked());
+}
+
+#[test]
+fn test_cardinality_no_zeros_small_estimator() {
+    // every register at 1 leaves no zeros while the raw estimator, 2 * alpha * m, is still under
+    // the small range cutoff of 5/2 * m.  The reference implementations use the raw estimator here.
+    for log_2m in [4, 11] {
+        let m = (1 << log_2m) as f64;
+        let settings = Settings::new(log_2m, 5, 0, true).unwrap();
+        let regs: BTreeMap<u32, u8> = (0..(1 << log_2m)).map(|i| (i, 1)).collect();
+        let expected = (settings.alpha_msquared / (m / 2.0)).ceil() as u64;
+        assert!((expected as f64) < settings.small_estimator_cutoff);
+
+        let hll = Hll::from_register_map(settings, &regs).unwrap();
+        assert!(matches!(hll, Hll::Dense(_)));
+        assert_eq!(expected, hll.cardinality());
+
+        let mut sparse = SparseRegisters::with_settings(&settings);
+        for (reg_num, value) in regs.iter() {
+            sparse.set_if_greater(RegisterIndex(*reg_num), RegisterValue(*value));
+        }
+        assert_eq!(expected, Hll::Sparse(sparse).cardinality());
+    }
+}
+
+#[test]
+fn test_union_counting() {
+    let explicit_settings = Settings::new(11, 5, 256, true).unwrap();
+    let sparse_settings = Settings::new(11, 5, 0, true).unwrap();
+    let dense_settings = Settings::new(11, 5, 0, false).unwrap();
+
+    for settings in [explicit_settings, sparse_settings, dense_settings] {
+        let mut other = Hll::new(settings);
+        for i in 0..50 {
+            other.add_raw(construct_hll_value(settings.log_2m, i * 3, (i % 7) + 1));
+        }
+
+        let mut hll = Hll::new(settings);
+        for i in 0..50 {
+            hll.add_raw(construct_hll_value(settings.log_2m, i * 2, 3));
+        }
+
+        let changed = hll.union_counting(true, &other).unwrap();
+        assert!(changed > 0);
+
+        for _ in 0..3 {
+            assert_eq!(0, hll.union_counting(true, &other).unwrap());
+        }
+    }
+}
+
+#[test]
+fn test_union_changed() {
+    let explicit_settings = Settings::new(11, 5, 256, true).unwrap();
+    let sparse_settings = Settings::new(11, 5, 0, true).unwrap();
+    let dense_settings = Settings::new(11, 5, 0, false).unwrap();
+
+    for settings in [explicit_settings, sparse_settings, dense_settings] {
+        let mut hll = Hll::new(settings);
+        let mut subset = Hll::new(settings);
+        for i in 0..100 {
+            hll.add(&i);
+            if i % 2 == 0 {
+                subset.add(&i);
+            }
+        }
+
+        let before = hll.clone();
+        assert!(!hll.union_changed(true, &subset).unwrap());
+        assert!(!hll.union_changed(true, &Hll::new(settings)).unwrap());
+        assert_eq!(before, hll);
+
+        let mut other = Hll::new(settings);
+        other.add("apple");
+        assert!(hll.union_changed(true, &other).unwrap());
+        assert!(!hll.union_changed(true, &other).unwrap());
+    }
+
+    // the storage changes even though no register is set
+    let mut hll = Hll::new(dense_settings);
+    assert!(matches!(hll, Hll::Empty(_)));
+    let dense = Hll::new(dense_settings).into_dense();
+    assert!(hll.union_changed(true, &dense).unwrap());
+    assert!(matches!(hll, Hll::Dense(_)));
+    assert!(!hll.union_changed(true, &dense).unwrap());
+}
+
+#[test]
+fn test_will_transition_soon() {
+    let settings = Settings::new(11, 5, 0, true).unwrap();
+    let threshold = settings.sparse_threshold.unwrap() as u32;
+
+    let mut hll = Hll::new(settings);
+    assert!(!hll.will_transition_soon(10));
+
+    for i in 0..threshold {
+        hll.add_raw(construct_hll_value(settings.log_2m, i, 1));
+
+        let remaining = (threshold - i) as usize;
+        assert!(!hll.will_transition_soon(remaining - 1), "register {}", i);
+        assert!(hll.will_transition_soon(remaining), "register {}", i);
+    }
+
+    hll.add_raw(construct_hll_value(settings.log_2m, threshold, 1));
+    assert!(matches!(hll, Hll::Dense(_)));
+    assert!(!hll.will_transition_soon(usize::MAX));
+
+    let hll = Hll::new(Settings::new(11, 5, 0, false).unwrap());
+    assert!(hll.will_transition_soon(1));
+}
+
+#[test]
+fn test_len() {
+    let settings = Settings::new(11, 5, 0, true).unwrap();
+    let mut hll = Hll::new(settings);
+    assert_eq!(0, hll.len());
+    assert!(hll.is_empty());
+
+    for i in 0..500 {
+        hll.add_raw(construct_hll_value(settings.log_2m, i, (i % 7) + 1));
+        assert_eq!(hll.cardinality() as usize, hll.len());
+    }
+    assert!(!hll.is_empty());
+}
+
+// touch counts would allocate 2^29 u32s
+#[cfg(not(feature = "touch-counts"))]
+#[test]
+#[ignore = "allocates about 670 MB, run with cargo test -- --ignored"]
+fn test_len_saturates() {
+    assert_eq!(usize::MAX, saturated_hll().len());
+}
+
+#[test]
+fn test_is_empty() {
+    for (explicit_threshold, sparse_enabled) in [(-1, true), (0, true), (0, false)] {
+        let settings = Settings::new(11, 5, explicit_threshold, sparse_enabled).unwrap();
+        let mut hll = Hll::new(settings);
+        assert!(hll.is_empty());
+
+        hll.add("apple");
+        assert!(!hll.is_empty());
+
+        // storage that has been cleared is empty without going back to Hll::Empty
+        hll.clear_in_place();
+        assert!(!matches!(hll, Hll::Empty(_)));
+        assert!(hll.is_empty());
+    }
+
+    let settings = Settings::new(11, 5, 0, false).unwrap();
+    assert!(Hll::Dense(DenseRegisters::with_settings(&settings)).is_empty());
+}
+
+#[test]
+fn test_from_storage() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+
+    let mut explicit = ExplicitStorage::with_settings(&settings);
+    explicit.set(1);
+    let hll = Hll::from(explicit.clone());
+    assert_eq!(Hll::Explicit(explicit), hll);
+    assert_eq!(1, hll.cardinality());
+
+    let mut sparse = SparseRegisters::with_settings(&settings);
+    sparse.set_if_greater(RegisterIndex(3), RegisterValue(1));
+    let hll: Hll = sparse.clone().into();
+    assert_eq!(Hll::Sparse(sparse), hll);
+    assert!(hll.cardinality() > 0);
+
+    let mut dense = DenseRegisters::with_settings(&settings);
+    dense.set_reg(3, 1);
+    let hll: Hll = dense.clone().into();
+    assert_eq!(Hll::Dense(dense), hll);
+    assert!(hll.cardinality() > 0);
+}
+
+#[test]
+fn test_bitor() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+
+    let mut a = Hll::new(settings);
+    let mut b = Hll::new(settings);
+    for i in 0..500 {
+        a.add_raw(construct_hll_value(settings.log_2m, i, (i % 7) + 1));
+        b.add_raw(construct_hll_value(settings.log_2m, i * 3, (i % 5) + 1));
+    }
+
+    let mut expected = a.clone();
+    expected.union(true, &b).unwrap();
+
+    assert_eq!(expected, &a | &b);
+
+    a |= &b;
+    assert_eq!(expected, a);
+}
+
+#[test]
+#[should_panic]
+fn test_bitor_mismatch() {
+    let a = Hll::new(Settings::new(11, 5, -1, true).unwrap());
+    let b = Hll::new(Settings::new(12, 5, -1, true).unwrap());
+
+    let _ = &a | &b;
+}
+
+#[test]
+fn test_cardinality_deterministic() {
+    let settings = Settings::new(11, 5, 0, true).unwrap();
+
+    let sparse = Hll::from_register_map(settings, &(0..100).map(|i| (i * 3, 1)).collect()).unwrap();
+    let dense = Hll::from_register_map(
+        settings,
+        &(0..(1 << settings.log_2m))
+            .map(|i| (i, ((i % 9) + 1) as u8))
+            .collect(),
+    )
+    .unwrap();
+    let large = Hll::from_register_map(
+        Settings::new(4, 3, 0, false).unwrap(),
+        &(0..16).map(|i| (i, 5)).collect(),
+    )
+    .unwrap();
+
+    // covers the small range correction, the raw estimator and the large range correction
+    for (hll, expected) in [(sparse, 103), (dense, 13290), (large, 421)] {
+        assert_eq!(expected, hll.cardinality_deterministic());
+        assert_eq!(hll.cardinality(), hll.cardinality_deterministic());
+    }
+}
+
+#[test]
+fn test_add_raw_all() {
+    use rand::prelude::*;
+    use rand::rngs::StdRng;
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let values: Vec<u64> = (0..5000).map(|_| rng.random()).collect();
+
+    for settings in [
+        Settings::new(11, 5, -1, true).unwrap(),
+        Settings::new(11, 5, 0, false).unwrap(),
+    ] {
+        let mut expected = Hll::new(settings);
+        for value in values.iter() {
+            expected.add_raw(*value);
+        }
+
+        let mut hll = Hll::new(settings);
+        hll.add_raw_all(&values);
+        assert_eq!(expected, hll);
+
+        // absorbing into an already populated hll
+        let mut hll = Hll::new(settings);
+        hll.add_raw_all(&values[..10]);
+        hll.add_raw_all(&values[10..]);
+        assert_eq!(expected, hll);
+    }
+}
+
+#[test]
+fn test_add_all_bytes() {
+    let items: Vec<String> = (0..5000).map(|i| format!("item-{i}")).collect();
+
+    for settings in [
+        Settings::new(11, 5, -1, true).unwrap(),
+        Settings::new(11, 5, 0, false).unwrap(),
+    ] {
+        let mut expected = Hll::new(settings);
+        for item in items.iter() {
+            expected.add_bytes(item.as_bytes());
+        }
+
+        let mut hll = Hll::new(settings);
+        hll.add_all_bytes(&items);
+        assert_eq!(expected, hll);
+
+        let (cardinality, std_error) = hll.cardinality_with_error();
+        let error = (cardinality as f64 - items.len() as f64).abs() / items.len() as f64;
+        assert!(error < 3.0 * std_error, "cardinality {cardinality}");
+    }
+}
+
+#[test]
+fn test_with_params() {
+    let hll = Hll::with_params(10, 4, -1, true).unwrap();
+    let expected = Hll::new(Settings::new(10, 4, -1, true).unwrap());
+    assert_eq!(expected, hll);
+
+    assert!(matches!(
+        Hll::with_params(3, 4, -1, true),
+        Err(SettingsError::Log2m)
+    ));
+}
+
+#[test]
+fn test_union_wider_reg_width() {
+    let narrow_regs: BTreeMap<u32, u8> = BTreeMap::from([(3, 2), (7, 31), (9, 30)]);
+
+    for sparse_enabled in [true, false] {
+        let narrow = Settings::new(11, 5, 0, sparse_enabled).unwrap();
+        let wide = Settings::new(11, 6, 0, sparse_enabled).unwrap();
+        let other = Hll::from_register_map(narrow, &narrow_regs).unwrap();
+
+        // strict unions still require identical settings
+        let mut hll = Hll::new(wide);
+        assert!(hll.union(true, &other).is_err());
+
+        let mut expected = narrow_regs.clone();
+        expected.insert(1, 40);
+
+        let mut empty = Hll::new(wide);
+        let mut populated =
+            Hll::from_register_map(wide, &BTreeMap::from([(1, 40), (7, 3)])).unwrap();
+
+        for (hll, expected) in [(&mut empty, &narrow_regs), (&mut populated, &expected)] {
+            hll.union(false, &other).unwrap();
+
+            assert_eq!(6, hll.settings().reg_width);
+            assert_eq!(expected, &register_map(hll));
+        }
+    }
+}
+
+fn register_map(hll: &Hll) -> BTreeMap<u32, u8> {
+    match hll {
+        Hll::Sparse(sparse) => sparse.iter().copied().collect(),
+        Hll::Dense(dense) => dense.iter().filter(|(_, v)| *v != 0).collect(),
+        _ => panic!("expected registers"),
+    }
+}
+
+#[test]
+fn test_to_from_bytes_minimal() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let mut hll = Hll::new(settings);
+
+    for i in 0..3000 {
+        // covers empty, explicit, sparse and dense storage
+        if matches!(i, 0 | 10 | 500 | 2999) {
+            let bytes = hll.to_bytes_minimal();
+            assert!(bytes.len() < hll.to_bytes().len());
+            assert_eq!(hll, Hll::from_bytes_minimal(settings, &bytes).unwrap());
+        }
+
+        hll.add_raw(construct_hll_value(settings.log_2m, i % 2048, (i % 9) + 1));
+    }
+    assert!(matches!(hll, Hll::Dense(_)));
+}
+
+#[test]
+fn test_add() {
+    let settings = Settings::new(11, 5, 0, true).unwrap();
+
+    let mut lhs = Hll::new(settings);
+    let mut rhs = Hll::new(settings);
+    for value in ["apple", "banana", "cherry"] {
+        lhs.add(value);
+        rhs.add(value);
+    }
+    assert_eq!(lhs, rhs);
+    assert_eq!(register_map(&lhs), register_map(&rhs));
+
+    let mut hll = Hll::new(Settings::new(11, 5, -1, true).unwrap());
+    hll.add("apple");
+    hll.add("apple");
+    assert_eq!(1, hll.cardinality());
+
+    let mut lhs = Hll::new(settings);
+    let mut rhs = Hll::new(settings);
+    lhs.add_bytes(b"apple");
+    rhs.add_raw(murmur3_64(b"apple"));
+    assert_eq!(lhs, rhs);
+
+    let mut lhs = Hll::new(settings);
+    let mut rhs = Hll::new(settings);
+    lhs.add(&42_i64);
+    rhs.add_raw(murmur3_64(&42_i64.to_le_bytes()));
+    assert_eq!(lhs, rhs);
+}
+
+#[test]
+fn test_cardinality_from_bytes() {
+    for (explicit_threshold, sparse_enabled) in [(-1, true), (0, true), (0, false)] {
+        let settings = Settings::new(11, 5, explicit_threshold, sparse_enabled).unwrap();
+        let mut hll = Hll::new(settings);
+
+        for i in 0..3000 {
+            let bytes = hll.to_bytes();
+            if i % 100 == 0 {
+                assert_eq!(
+                    Hll::from_bytes(&bytes).unwrap().cardinality(),
+                    Hll::cardinality_from_bytes(&bytes).unwrap()
+                );
+            }
+
+            hll.add(&i);
+        }
+    }
+
+    // dense storage that is missing trailing registers
+    let settings = Settings::new(11, 5, 0, false).unwrap();
+    let mut hll = Hll::new(settings);
+    hll.add_raw(construct_hll_value(settings.log_2m, 1, 3));
+    let bytes = hll.to_bytes();
+    assert!(matches!(
+        Hll::cardinality_from_bytes(&bytes[..10]),
+        Err(HllError::Truncated {
+            expected: 1280,
+            found: 7
+        })
+    ));
+}
+
+#[test]
+fn test_from_bytes_invalid_header() {
+    let bytes = Hll::new(Settings::new(11, 5, -1, true).unwrap()).to_bytes();
+
+    for type_id in [0, 5, 0x0f] {
+        let mut bytes = bytes.clone();
+        bytes[0] = 0x10 | type_id;
+        assert!(matches!(
+            Hll::from_bytes(&bytes),
+            Err(HllError::InvalidTypeId(t)) if t == type_id
+        ));
+    }
+
+    for version in [0, 2, 0x0f] {
+        let mut bytes = bytes.clone();
+        bytes[0] = (version << 4) | 0x01;
+        assert!(matches!(
+            Hll::from_bytes(&bytes),
+            Err(HllError::Version(v)) if v == version
+        ));
+    }
+}
+
+#[test]
+fn test_peek_header() {
+    let settings = Settings::new(14, 6, -1, true).unwrap();
+    let mut hll = Hll::new(settings).into_dense();
+    for i in 0..1000 {
+        hll.add(&i);
+    }
+
+    let bytes = hll.to_bytes();
+    assert_eq!(settings.serialized_header_bytes(4), bytes[..3]);
+    assert_eq!((4, settings), Hll::peek_header(&bytes).unwrap());
+    // only the header is read
+    assert_eq!((4, settings), Hll::peek_header(&bytes[..3]).unwrap());
+    assert_eq!(
+        (4, settings),
+        Hll::peek_header(&hll.to_bytes_with_checksum()).unwrap()
+    );
+    assert_eq!(
+        (1, settings),
+        Hll::peek_header(&Hll::new(settings).to_bytes()).unwrap()
+    );
+
+    assert!(matches!(
+        Hll::peek_header(&bytes[..2]),
+        Err(HllError::Truncated {
+            expected: 3,
+            found: 2
+        })
+    ));
+    assert!(matches!(
+        Hll::peek_header(&[0x24, bytes[1], bytes[2]]),
+        Err(HllError::Version(2))
+    ));
+    assert!(matches!(
+        Hll::peek_header(&[0x15, bytes[1], bytes[2]]),
+        Err(HllError::InvalidTypeId(5))
+    ));
+}
+
+#[test]
+fn test_read_one() {
+    let empty = Hll::new(Settings::new(11, 5, -1, true).unwrap());
+    let mut dense = Hll::new(Settings::new(11, 5, 0, false).unwrap()).into_dense();
+    let mut wide = Hll::new(Settings::new(14, 6, 0, false).unwrap()).into_dense();
+    for i in 0..1000 {
+        dense.add(&i);
+        wide.add(&-i);
+    }
+
+    let mut stream = dense.to_bytes();
+    stream.extend(empty.to_bytes());
+    stream.extend(wide.to_bytes_with_checksum());
+
+    let mut buf = &stream[..];
+    let mut hlls = Vec::new();
+    while !buf.is_empty() {
+        let (hll, len) = Hll::read_one(buf).unwrap();
+        hlls.push(hll);
+        buf = &buf[len..];
+    }
+    assert_eq!(vec![dense.clone(), empty, wide], hlls);
+
+    let bytes = dense.to_bytes();
+    assert!(matches!(
+        Hll::read_one(&bytes[..bytes.len() - 1]),
+        Err(HllError::Truncated { .. })
+    ));
+
+    // explicit and sparse storage don't record their length
+    let mut hll = Hll::new(Settings::new(11, 5, -1, true).unwrap());
+    hll.add(&1);
+    assert!(matches!(
+        Hll::read_one(&hll.to_bytes()),
+        Err(HllError::UnknownLength(2))
+    ));
+    for i in 0..300 {
+        hll.add(&i);
+    }
+    assert!(matches!(hll, Hll::Sparse(_)));
+    assert!(matches!(
+        Hll::read_one(&hll.to_bytes()),
+        Err(HllError::UnknownLength(3))
+    ));
+}
+
+#[test]
+fn test_from_bytes_truncated() {
+    for len in 0..3 {
+        assert!(matches!(
+            Hll::from_bytes(&[0x11, 0x8b, 0x7f][..len]),
+            Err(HllError::Truncated { expected: 3, found }) if found == len
+        ));
+    }
+    assert!(matches!(
+        Hll::from_bytes_minimal(Settings::new(11, 5, 0, true).unwrap(), &[]),
+        Err(HllError::Truncated {
+            expected: 1,
+            found: 0
+        })
+    ));
+
+    for (explicit_threshold, sparse_enabled) in [(-1, true), (0, true), (0, false)] {
+        let settings = Settings::new(11, 5, explicit_threshold, sparse_enabled).unwrap();
+        let mut hll = Hll::new(settings);
+        for i in 0..3 {
+            hll.add(&i);
+        }
+
+        let bytes = hll.to_bytes();
+        for len in 3..bytes.len() {
+            match Hll::from_bytes(&bytes[..len]) {
+                // whole values and sparse entries can't be told apart from a shorter Hll
+                Ok(Hll::Sparse(_)) => assert_eq!(0, ((len - 3) * 8) % 16),
+                Ok(Hll::Explicit(_)) => assert_eq!(0, (len - 3) % 8),
+                Ok(_) => panic!("truncated {} to {} bytes", hll.type_id(), len),
+                Err(HllError::Truncated { .. }) => assert_eq!(4, hll.type_id()),
+                Err(HllError::StorageLength { type_id, .. }) => assert_eq!(hll.type_id(), type_id),
+                Err(err) => panic!("unexpected error {}", err),
+            }
+        }
+
+        let mut bytes = bytes;
+        bytes.push(0);
+        match Hll::from_bytes(&bytes) {
+            Ok(Hll::Sparse(_)) => {}
+            Err(HllError::StorageLength { type_id, .. }) => assert_eq!(hll.type_id(), type_id),
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+
+    assert!(matches!(
+        Hll::from_bytes(&[0x11, 0x8b, 0x7f, 0x00]),
+        Err(HllError::StorageLength { type_id: 1, len: 1 })
+    ));
+}
+
+#[test]
+fn test_intersect_cardinality() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let hll_from = |values: std::ops::Range<u32>| {
+        let mut hll = Hll::new(settings);
+        values.for_each(|i| hll.add(&i));
+        hll
+    };
+
+    // identical
+    let hll = hll_from(0..10_000);
+    assert_eq!(hll.cardinality(), hll.intersect_cardinality(&hll).unwrap());
+
+    // disjoint
+    let lhs = hll_from(0..10_000);
+    let rhs = hll_from(10_000..20_000);
+    assert!(lhs.intersect_cardinality(&rhs).unwrap() < 1_000);
+
+    // partially overlapping
+    let rhs = hll_from(5_000..15_000);
+    let intersection = lhs.intersect_cardinality(&rhs).unwrap();
+    assert!(
+        (4_000..6_000).contains(&intersection),
+        "intersection {}",
+        intersection
+    );
+
+    // explicit storage is exact
+    let lhs = hll_from(0..20);
+    let rhs = hll_from(10..50);
+    assert_eq!(10, lhs.intersect_cardinality(&rhs).unwrap());
+    assert_eq!(0, lhs.intersect_cardinality(&Hll::new(settings)).unwrap());
+
+    let other = Hll::new(Settings::new(10, 5, -1, true).unwrap());
+    assert!(matches!(
+        lhs.intersect_cardinality(&other),
+        Err(HllError::Settings(SettingsError::MisMatch))
+    ));
+}
+
+#[test]
+fn test_jaccard() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let hll_from = |values: std::ops::Range<u32>| {
+        let mut hll = Hll::new(settings);
+        values.for_each(|i| hll.add(&i));
+        hll
+    };
+
+    let hll = hll_from(0..10_000);
+    assert_eq!(1.0, hll.jaccard(&hll).unwrap());
+
+    let other = hll_from(10_000..20_000);
+    assert!(hll.jaccard(&other).unwrap() < 0.05);
+
+    let other = hll_from(5_000..15_000);
+    let jaccard = hll.jaccard(&other).unwrap();
+    assert!((0.25..0.42).contains(&jaccard), "jaccard {}", jaccard);
+
+    assert_eq!(
+        0.0,
+        Hll::new(settings).jaccard(&Hll::new(settings)).unwrap()
+    );
+    assert_eq!(
+        10.0 / 30.0,
+        hll_from(0..20).jaccard(&hll_from(10..30)).unwrap()
+    );
+}
+
+#[test]
+fn test_union_many() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let hll_from = |values: std::ops::Range<u32>, settings: Settings| {
+        let mut hll = Hll::new(settings);
+        values.for_each(|i| hll.add(&i));
+        hll
+    };
+
+    let empty = Hll::new(settings);
+    let explicit = hll_from(0..10, settings);
+    let sparse = hll_from(100..200, Settings::new(11, 5, 0, true).unwrap());
+    let dense = hll_from(1000..1100, Settings::new(11, 5, 0, false).unwrap());
+    assert!(matches!(explicit, Hll::Explicit(_)));
+    assert!(matches!(sparse, Hll::Sparse(_)));
+    assert!(matches!(dense, Hll::Dense(_)));
+
+    let inputs = [
+        vec![
+            empty.clone(),
+            explicit.clone(),
+            sparse.clone(),
+            dense.clone(),
+        ],
+        vec![
+            dense.clone(),
+            sparse.clone(),
+            explicit.clone(),
+            empty.clone(),
+        ],
+        vec![explicit.clone(), sparse.clone()],
+        vec![explicit.clone(), empty.clone()],
+        vec![],
+    ];
+
+    for start in [&empty, &explicit, &sparse, &dense] {
+        for others in inputs.iter() {
+            let mut expected = start.clone();
+            for other in others {
+                expected.union(false, other).unwrap();
+            }
+
+            let mut hll = start.clone();
+            hll.union_many(false, others).unwrap();
+            match expected {
+                Hll::Empty(_) | Hll::Explicit(_) => assert_eq!(expected, hll),
+                _ => assert_eq!(register_map(&expected), register_map(&hll)),
+            }
+            assert_eq!(expected.cardinality(), hll.cardinality());
+        }
+    }
+
+    // nothing is merged when an input doesn't fit
+    let mismatched = hll_from(0..10, Settings::new(10, 5, 0, false).unwrap());
+    let mut hll = explicit.clone();
+    assert!(
+        hll.union_many(true, &[dense.clone(), mismatched.clone()])
+            .is_err()
+    );
+    assert!(hll.union_many(false, &[dense.clone(), mismatched]).is_err());
+    assert_eq!(explicit, hll);
+
+    let mismatched = hll_from(0..10, Settings::new(10, 5, 0, true).unwrap());
+    assert!(matches!(mismatched, Hll::Sparse(_)));
+    let mut hll = sparse.clone();
+    assert!(
+        hll.union_many(true, &[explicit.clone(), mismatched])
+            .is_err()
+    );
+    assert_eq!(sparse, hll);
+}
+
+#[test]
+fn test_from_iter() {
+    // small integers aren't well distributed hashes, hash them first
+    let hll: Hll = (0..1000_u64)
+        .map(|i| murmur3_64(&i.to_le_bytes()))
+        .collect();
+    assert_eq!(SettingsBuilder::default().build().unwrap(), *hll.settings());
+    assert!(
+        hll.cardinality().abs_diff(1000) < 50,
+        "{}",
+        hll.cardinality()
+    );
+
+    let settings = Settings::new(11, 5, 0, false).unwrap();
+    let values: Vec<u64> = (0..1000_u64)
+        .map(|i| murmur3_64(&i.to_le_bytes()))
+        .collect();
+    let mut expected = Hll::new(settings);
+    values.iter().for_each(|value| expected.add_raw(*value));
+    assert_eq!(expected, Hll::from_iter_with_settings(settings, values));
+
+    let hll: Hll = [0_u64].into_iter().collect();
+    assert!(hll.is_empty());
+}
+
+#[test]
+fn test_extend() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let values: Vec<u64> = (0..3000_u64)
+        .map(|i| murmur3_64(&i.to_le_bytes()))
+        .collect();
+
+    // extending in steps passes through explicit, sparse and dense storage
+    let mut expected = Hll::new(settings);
+    let mut hll = Hll::new(settings);
+    for chunk in values.chunks(100) {
+        chunk.iter().for_each(|value| expected.add_raw(*value));
+        hll.extend(chunk.iter().copied());
+        assert_eq!(expected, hll);
+    }
+    assert!(matches!(hll, Hll::Dense(_)));
+}
+
+#[test]
+fn test_compact() {
+    let settings = Settings::new(11, 5, 0, true).unwrap();
+    let mut dense = DenseRegisters::with_settings(&settings);
+    for (reg_num, value) in [(3, 1), (100, 4), (2047, 7)] {
+        dense.set_reg(reg_num, value);
+    }
+
+    let mut hll = Hll::Dense(dense.clone());
+    let cardinality = hll.cardinality();
+    hll.compact();
+    assert!(matches!(hll, Hll::Sparse(_)));
+    assert_eq!(register_map(&Hll::Dense(dense)), register_map(&hll));
+    assert_eq!(cardinality, hll.cardinality());
+    assert_eq!(hll, Hll::from_bytes(&hll.to_bytes()).unwrap());
+
+    // too many registers for sparse storage
+    let mut hll = Hll::new(settings);
+    (0..5000).for_each(|i| hll.add(&i));
+    assert!(matches!(hll, Hll::Dense(_)));
+    let expected = hll.clone();
+    hll.compact();
+    assert_eq!(expected, hll);
+
+    // sparse storage disabled
+    let settings = Settings::new(11, 5, 0, false).unwrap();
+    let mut hll = Hll::new(settings);
+    hll.add("apple");
+    hll.compact();
+    assert!(matches!(hll, Hll::Dense(_)));
+}
+
+#[test]
+fn test_logically_eq() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let mut hll = Hll::new(settings);
+    assert!(hll.logically_eq(&Hll::new(settings)));
+
+    for i in 0..200 {
+        hll.add(&i);
+    }
+    assert!(matches!(hll, Hll::Sparse(_)));
+
+    let Hll::Sparse(sparse_registers) = &hll else {
+        unreachable!()
+    };
+    let dense = Hll::Dense(sparse_registers.to_dense(None));
+    assert_ne!(hll, dense);
+    assert!(hll.logically_eq(&dense));
+    assert!(dense.logically_eq(&hll));
+
+    // explicit storage compares by the registers its values set
+    let mut explicit = Hll::new(settings);
+    explicit.add("apple");
+    let mut sparse = Hll::new(Settings::new(11, 5, 0, true).unwrap());
+    sparse.add("apple");
+    assert!(explicit.logically_eq(&sparse));
+
+    hll.add("apple");
+    assert!(!hll.logically_eq(&dense));
+    assert!(!hll.logically_eq(&Hll::new(settings)));
+    assert!(!Hll::new(settings).logically_eq(&Hll::new(Settings::new(12, 5, -1, true).unwrap())));
+}
+
+#[test]
+fn test_is_subset_of() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let empty = Hll::new(settings);
+    assert!(empty.is_subset_of(&empty).unwrap());
+
+    // explicit, sparse and dense
+    for n in [10, 200, 5000] {
+        let mut hll = Hll::new(settings);
+        let mut disjoint = Hll::new(settings);
+        for i in 0..n {
+            hll.add(&i);
+            disjoint.add(&(i + n));
+        }
+
+        let mut union = hll.clone();
+        union.union(true, &disjoint).unwrap();
+
+        assert!(hll.is_subset_of(&hll).unwrap());
+        assert!(hll.is_subset_of(&union).unwrap());
+        assert!(disjoint.is_subset_of(&union).unwrap());
+        assert!(empty.is_subset_of(&hll).unwrap());
+        assert!(!hll.is_subset_of(&empty).unwrap());
+        assert!(!union.is_subset_of(&hll).unwrap());
+
+        assert!(!hll.is_subset_of(&disjoint).unwrap());
+        assert!(!disjoint.is_subset_of(&hll).unwrap());
+    }
+
+    // storage doesn't matter
+    let mut hll = Hll::new(settings);
+    for i in 0..200 {
+        hll.add(&i);
+    }
+    let dense = hll.clone().into_dense();
+    assert!(hll.is_subset_of(&dense).unwrap());
+    assert!(dense.is_subset_of(&hll).unwrap());
+
+    assert!(matches!(
+        empty.is_subset_of(&Hll::new(Settings::new(12, 5, -1, true).unwrap())),
+        Err(HllError::Settings(SettingsError::MisMatch))
+    ));
+}
+
+#[test]
+fn test_cardinality_with_error() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let mut hll = Hll::new(settings);
+    assert_eq!((0, 0.0), hll.cardinality_with_error());
+
+    for i in 0..10 {
+        hll.add(&i);
+    }
+    assert!(matches!(hll, Hll::Explicit(_)));
+    assert_eq!((10, 0.0), hll.cardinality_with_error());
+
+    for i in 0..5000 {
+        hll.add(&i);
+    }
+    let (cardinality, error) = hll.cardinality_with_error();
+    assert_eq!(hll.cardinality(), cardinality);
+    assert_eq!(1.04 / (2048_f64).sqrt(), error);
+}
+
+#[test]
+fn test_registers() {
+    let settings = Settings::new(4, 5, 0, false).unwrap();
+    let mut dense = DenseRegisters::with_settings(&settings);
+    for (reg_num, value) in [(0, 3), (5, 1), (15, 31)] {
+        dense.set_reg(reg_num, value);
+    }
+
+    let expected: Vec<(u32, u8)> = (0..16)
+        .map(|i| match i {
+            0 => (0, 3),
+            5 => (5, 1),
+            15 => (15, 31),
+            _ => (i, 0),
+        })
+        .collect();
+    assert_eq!(expected, Hll::Dense(dense).registers().collect::<Vec<_>>());
+
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    assert_eq!(0, Hll::new(settings).registers().count());
+
+    let mut explicit = Hll::new(settings);
+    let mut sparse = Hll::new(Settings::new(11, 5, 0, true).unwrap());
+    for i in 0..10 {
+        explicit.add(&i);
+        sparse.add(&i);
+    }
+    assert!(matches!(explicit, Hll::Explicit(_)));
+    assert!(matches!(sparse, Hll::Sparse(_)));
+    assert_eq!(
+        register_map(&sparse),
+        explicit.registers().collect::<BTreeMap<_, _>>()
+    );
+    assert_eq!(
+        register_map(&sparse),
+        sparse.registers().collect::<BTreeMap<_, _>>()
+    );
+}
+
+#[test]
+fn test_heap_size() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let mut hll = Hll::new(settings);
+    assert_eq!(0, hll.heap_size());
+
+    hll.add("apple");
+    assert!(matches!(hll, Hll::Explicit(_)));
+    assert!(hll.heap_size() >= 8);
+
+    let mut hll = Hll::new(Settings::new(11, 5, 0, true).unwrap());
+    for i in 0..100 {
+        hll.add(&i);
+    }
+    let Hll::Sparse(sparse_registers) = &hll else {
+        panic!("expected sparse storage");
+    };
+    assert!(hll.heap_size() >= sparse_registers.len() * 8);
+}
+
+#[test]
+fn test_shrink_to_fit() {
+    // explicit storage reserves 512 slots for its threshold of 160 values
+    for (explicit_threshold, sparse_enabled) in [(-1, true), (0, true)] {
+        let settings = Settings::new(11, 5, explicit_threshold, sparse_enabled).unwrap();
+        let mut hll = Hll::new(settings);
+        for i in 0..100 {
+            hll.add(&i);
+        }
+        assert!(matches!(hll, Hll::Explicit(_) | Hll::Sparse(_)));
+
+        let populated = hll.heap_size();
+        hll.clear_in_place();
+        assert_eq!(populated, hll.heap_size());
+
+        hll.add(&1);
+        hll.shrink_to_fit();
+        assert!(hll.heap_size() < populated / 10, "{}", hll.heap_size());
+
+        let mut expected = Hll::new(settings);
+        expected.add(&1);
+        assert_eq!(expected, hll);
+    }
+
+    // a single explicit value fits in the smallest table of 2 slots
+    let mut hll = Hll::new(Settings::new(11, 5, -1, true).unwrap());
+    hll.add(&1);
+    assert_eq!(512 * 8, hll.heap_size());
+    hll.shrink_to_fit();
+    assert_eq!(2 * 8, hll.heap_size());
+
+    // dense storage is always exactly sized
+    let mut hll = Hll::new(Settings::new(11, 5, 0, false).unwrap());
+    hll.add(&1);
+    let heap_size = hll.heap_size();
+    hll.shrink_to_fit();
+    assert_eq!(heap_size, hll.heap_size());
+}
+
+#[test]
+fn test_into_dense_sparse() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let mut explicit = Hll::new(settings);
+    for i in 0..50 {
+        explicit.add(&i);
+    }
+    assert!(matches!(explicit, Hll::Explicit(_)));
+
+    let dense = explicit.clone().into_dense();
+    assert!(matches!(dense, Hll::Dense(_)));
+    assert!(explicit.logically_eq(&dense));
+    assert_eq!(
+        dense.cardinality(),
+        dense.clone().into_dense().cardinality()
+    );
+
+    let sparse = dense.clone().into_sparse().unwrap();
+    assert!(matches!(sparse, Hll::Sparse(_)));
+    assert!(sparse.logically_eq(&dense));
+    assert_eq!(dense.cardinality(), sparse.cardinality());
+    assert_eq!(sparse, explicit.clone().into_sparse().unwrap());
+    assert_eq!(sparse, sparse.clone().into_dense().into_sparse().unwrap());
+
+    assert!(matches!(
+        Hll::new(settings).into_sparse().unwrap(),
+        Hll::Sparse(_)
+    ));
+    assert!(Hll::new(settings).into_dense().is_empty());
+
+    let mut full = Hll::new(settings);
+    for i in 0..5000 {
+        full.add(&i);
+    }
+    assert!(matches!(
+        full.into_sparse(),
+        Err(HllError::SparseThreshold(_))
+    ));
+
+    let no_sparse = Hll::new(Settings::new(11, 5, -1, false).unwrap());
+    assert!(matches!(
+        no_sparse.into_sparse(),
+        Err(HllError::SparseDisabled)
+    ));
+}
+
+#[test]
+fn test_union_cardinality() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let mut dense = Hll::new(settings);
+    (0..10_000).for_each(|i: u32| dense.add(&i));
+    let mut explicit = Hll::new(settings);
+    (20_000..20_010).for_each(|i: u32| explicit.add(&i));
+    assert!(matches!(dense, Hll::Dense(_)));
+    assert!(matches!(explicit, Hll::Explicit(_)));
+
+    let (dense_before, explicit_before) = (dense.clone(), explicit.clone());
+
+    let mut union = dense.clone();
+    union.union(true, &explicit).unwrap();
+    assert_eq!(
+        union.cardinality(),
+        dense.union_cardinality(&explicit).unwrap()
+    );
+    assert_eq!(
+        union.cardinality(),
+        explicit.union_cardinality(&dense).unwrap()
+    );
+
+    assert_eq!(dense_before, dense);
+    assert_eq!(explicit_before, explicit);
+
+    let other = Hll::new(Settings::new(10, 5, -1, true).unwrap());
+    assert!(dense.union_cardinality(&other).is_err());
+}
+
+#[test]
+fn test_write_to() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let mut hll = Hll::new(settings);
+    let mut scratch = [0xff_u8; 4096];
+
+    for count in [0, 10, 200, 5000] {
+        for i in hll.cardinality()..count {
+            hll.add(&i);
+        }
+
+        let bytes = hll.to_bytes();
+        assert_eq!(bytes.len(), hll.serialized_len());
+
+        let len = hll.write_to(&mut scratch).unwrap();
+        assert_eq!(bytes, &scratch[..len]);
+
+        let mut exact = vec![0; hll.serialized_len()];
+        assert_eq!(exact.len(), hll.write_to(&mut exact).unwrap());
+        assert_eq!(bytes, exact);
+
+        exact.pop();
+        assert!(matches!(
+            hll.write_to(&mut exact),
+            Err(HllError::Truncated { .. })
+        ));
+    }
+}
+
+#[test]
+fn test_add_with_hasher() {
+    use std::collections::hash_map::RandomState;
+    use std::hash::BuildHasherDefault;
+
+    use crate::Murmur3Hasher;
+
+    let settings = Settings::new(11, 5, 0, true).unwrap();
+    let build_hasher = RandomState::new();
+    let (mut lhs, mut rhs) = (Hll::new(settings), Hll::new(settings));
+    for i in 0..1000 {
+        lhs.add_with_hasher(&i, &build_hasher);
+        rhs.add_with_hasher(&i, &build_hasher);
+    }
+    assert!(lhs.registers().eq(rhs.registers()));
+
+    // add uses murmur3
+    let (mut default, mut murmur) = (Hll::new(settings), Hll::new(settings));
+    for i in 0..1000 {
+        default.add(&i);
+        murmur.add_with_hasher(&i, &BuildHasherDefault::<Murmur3Hasher>::default());
+    }
+    assert_eq!(default, murmur);
+}
+
+#[test]
+fn test_union_downsample() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let values: Vec<u64> = (0..20_000).map(|_| rng.random()).collect();
+
+    for (count, explicit_threshold) in [(100, 0), (20_000, 0), (100, -1)] {
+        let low_settings = Settings::new(11, 5, explicit_threshold, true).unwrap();
+        let high_settings = Settings::new(12, 5, explicit_threshold, true).unwrap();
+        let low = Hll::from_iter_with_settings(low_settings, values[..count].iter().copied());
+        let high = Hll::from_iter_with_settings(high_settings, values[..count].iter().copied());
+
+        let mut downsampled = Hll::new(low_settings);
+        downsampled.union_downsample(&high).unwrap();
+        assert!(low.logically_eq(&downsampled), "{}", count);
+        assert_eq!(low.cardinality(), downsampled.cardinality());
+
+        assert!(matches!(
+            high.clone().union_downsample(&low),
+            Err(HllError::Settings(_))
+        ));
+    }
+}
+
+#[test]
+fn test_display() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let mut hll = Hll::new(settings);
+    assert_eq!(
+        "HLL(empty, log_2m=11, reg_width=5, cardinality≈0, heap=0B)",
+        hll.to_string()
+    );
+
+    for i in 0..3 {
+        hll.add(&i);
+    }
+    let display = hll.to_string();
+    assert!(display.starts_with("HLL(explicit, log_2m=11, reg_width=5, cardinality≈3, heap="));
+
+    let hll = Hll::new(Settings::new(11, 5, 0, false).unwrap()).into_dense();
+    // touch counts are kept alongside the registers
+    let heap = if cfg!(feature = "touch-counts") {
+        hll.heap_size()
+    } else {
+        1280
+    };
+    assert_eq!(
+        format!("HLL(dense, log_2m=11, reg_width=5, cardinality≈0, heap={heap}B)"),
+        hll.to_string()
+    );
+}
+
+#[test]
+fn test_checksum() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let mut hll = Hll::new(settings);
+
+    for count in [0, 10, 200, 5000] {
+        for i in 0..count {
+            hll.add(&i);
+        }
+
+        let plain = hll.to_bytes();
+        let checksummed = hll.to_bytes_with_checksum();
+        assert_eq!(plain.len() + 4, checksummed.len());
+        assert_eq!(hll, Hll::from_bytes(&plain).unwrap());
+        assert_eq!(hll, Hll::from_bytes(&checksummed).unwrap());
+        assert_eq!(
+            hll.cardinality(),
+            Hll::cardinality_from_bytes(&checksummed).unwrap()
+        );
+
+        // flipping any bit, including the settings, is detected
+        for idx in [1, checksummed.len() / 2, checksummed.len() - 1] {
+            let mut corrupted = checksummed.clone();
+            corrupted[idx] ^= 0x10;
+            assert!(
+                matches!(Hll::from_bytes(&corrupted), Err(HllError::Checksum { .. })),
+                "count {} idx {}",
+                count,
+                idx
+            );
+        }
+    }
+
+    let checksummed = Hll::new(settings).to_bytes_with_checksum();
+    assert!(matches!(
+        Hll::from_bytes(&checksummed[..5]),
+        Err(HllError::Truncated { .. })
+    ));
+}
+
+#[test]
+fn test_explicit_storage() {
+    use crate::Storage;
+
+    let settings = Settings::new(11, 5, 5000, true).unwrap();
+    let mut rng = StdRng::seed_from_u64(9);
+    let values: Vec<u64> = (0..5000).map(|_| rng.random()).collect();
+
+    // the table grows past its first reservation and every value is only counted once
+    let mut explicit_storage = ExplicitStorage::with_settings(&settings);
+    for value in values
+        .iter()
+        .chain(values.iter())
+        .chain([0, 0, 1, 1].iter())
+    {
+        explicit_storage.set(*value);
+    }
+    assert_eq!(5002, explicit_storage.len());
+    assert!(explicit_storage.is_full());
+
+    let mut expected: Vec<i64> = values.iter().map(|v| *v as i64).chain([0, 1]).collect();
+    expected.sort_unstable();
+    let sorted: Vec<i64> = explicit_storage.iter().map(|v| v as i64).collect();
+    assert_eq!(expected, sorted);
+
+    // equality doesn't depend on the order values were added in or the size of the table
+    let mut reversed = ExplicitStorage::with_settings(&settings);
+    for value in expected.iter().rev() {
+        reversed.set(*value as u64);
+    }
+    reversed.shrink_to_fit();
+    assert_eq!(explicit_storage, reversed);
+    reversed.clear();
+    assert_ne!(explicit_storage, reversed);
+
+    // values from other implementations can be unsorted and repeated
+    let buf: Vec<u8> = [3_i64, -7, 3, 0]
+        .iter()
+        .flat_map(|v| v.to_be_bytes())
+        .collect();
+    let from_bytes = ExplicitStorage::from_bytes(&settings, &buf).unwrap();
+    assert_eq!(3, from_bytes.len());
+    assert_eq!(
+        vec![-7_i64 as u64, 0, 3],
+        from_bytes.iter().collect::<Vec<_>>()
+    );
+
+    // the table is reserved for the threshold on the first insert, up to 1024 slots
+    for (explicit_threshold, slots) in [(160, 512), (131_072, 1024)] {
+        let settings = Settings::new(11, 5, explicit_threshold, true).unwrap();
+        let mut explicit_storage = ExplicitStorage::with_settings(&settings);
+        assert_eq!(0, explicit_storage.heap_size());
+        explicit_storage.set(1);
+        assert_eq!(slots * 8, explicit_storage.heap_size());
+    }
+}
+
+#[test]
+fn test_explicit_into_sparse() {
+    let mut rng = StdRng::seed_from_u64(3);
+    for log_2m in [4, 11, 16] {
+        let settings = Settings::new(log_2m, 5, 0, true).unwrap();
+        let mut explicit_storage = ExplicitStorage::with_settings(&settings);
+        let mut expected = Hll::Sparse(SparseRegisters::with_settings(&settings));
+
+        for _ in 0..200 {
+            let value: u64 = rng.random();
+            explicit_storage.set(value);
+            expected.add_raw(value);
+        }
+
+        let sparse_registers = explicit_storage.into_sparse();
+        if let Hll::Sparse(expected) = &expected {
+            assert_eq!(expected, &sparse_registers);
+        } else {
+            assert!(expected.logically_eq(&Hll::Sparse(sparse_registers)));
+        }
+        assert_eq!(expected, explicit_storage.as_registers());
+    }
+}
+
+#[test]
+fn test_new_dense_sparse() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+
+    let mut hll = Hll::new_dense(settings).unwrap();
+    assert!(matches!(hll, Hll::Dense(_)));
+    assert_eq!(0, hll.cardinality());
+    let bytes = hll.to_bytes();
+    assert_eq!(0x14, bytes[0]);
+    assert_eq!(3 + 1280, bytes.len());
+
+    hll.add(&1);
+    assert!(matches!(hll, Hll::Dense(_)));
+    assert!(!hll.is_empty());
+
+    let mut hll = Hll::new_sparse(settings).unwrap();
+    assert!(matches!(hll, Hll::Sparse(_)));
+    assert_eq!(0, hll.cardinality());
+    assert_eq!(vec![0x13, 0x8b, 0x7f], hll.to_bytes());
+    hll.add(&1);
+    assert!(matches!(hll, Hll::Sparse(_)));
+
+    assert!(matches!(
+        Hll::new_sparse(Settings::new(11, 5, -1, false).unwrap()),
+        Err(HllError::SparseDisabled)
+    ));
+}
+
+#[test]
+fn test_union_explicit_lopsided() {
+    let settings = Settings::new(11, 5, 1000, true).unwrap();
+    let mut rng = StdRng::seed_from_u64(5);
+    let large: Vec<u64> = (0..900).map(|_| rng.random()).collect();
+    let small: Vec<u64> = large[..5].iter().copied().chain([1, 2, u64::MAX]).collect();
+
+    let mut expected = Hll::new(settings);
+    large
+        .iter()
+        .chain(small.iter())
+        .for_each(|v| expected.add_raw(*v));
+    assert!(matches!(expected, Hll::Explicit(_)));
+
+    let (large, small) = (
+        Hll::from_iter_with_settings(settings, large),
+        Hll::from_iter_with_settings(settings, small),
+    );
+
+    let mut lhs = small.clone();
+    assert_eq!(895, lhs.union_counting(true, &large).unwrap());
+    assert_eq!(expected.to_bytes(), lhs.to_bytes());
+
+    let mut rhs = large.clone();
+    assert_eq!(3, rhs.union_counting(true, &small).unwrap());
+    assert_eq!(expected.to_bytes(), rhs.to_bytes());
+
+    // the small side keeps its own settings when it takes over the large side's values
+    let wider = Settings::new(11, 5, 2000, true).unwrap();
+    let mut lhs = small.clone();
+    let mut rng = StdRng::seed_from_u64(5);
+    let large = Hll::from_iter_with_settings(wider, (0..900).map(|_| rng.random::<u64>()));
+    assert_eq!(895, lhs.union_counting(false, &large).unwrap());
+    assert_eq!(settings, *lhs.settings());
+    assert_eq!(expected.to_bytes(), lhs.to_bytes());
+}
+
+#[test]
+fn test_relative_error_estimate() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let std_error = settings.expected_std_error();
+
+    let mut hll = Hll::new(settings);
+    assert_eq!(0.0, hll.relative_error_estimate());
+    for i in 0..100 {
+        hll.add(&i);
+    }
+    assert!(matches!(hll, Hll::Explicit(_)));
+    assert_eq!(0.0, hll.relative_error_estimate());
+
+    // linear counting is at least as accurate as the standard error while few registers are set
+    for i in 100..1000 {
+        hll.add(&i);
+    }
+    assert_eq!(std_error, hll.relative_error_estimate());
+
+    // but gets worse as the load approaches the small range cutoff of 2.5m
+    for i in 1000..4500 {
+        hll.add(&i);
+    }
+    let small_range_error = hll.relative_error_estimate();
+    assert!(small_range_error > std_error, "{}", small_range_error);
+
+    for i in 4500..50_000 {
+        hll.add(&i);
+    }
+    assert_eq!(std_error, hll.relative_error_estimate());
+
+    // 2 bit registers reach the large range correction quickly
+    let settings = Settings::new(11, 2, 0, false).unwrap();
+    let mut hll = Hll::new(settings);
+    for i in 0..20_000 {
+        hll.add(&i);
+    }
+    assert!(hll.relative_error_estimate() > settings.expected_std_error());
+}
+
+#[test]
+fn test_clone_empty() {
+    let settings = Settings::new(11, 5, -1, true)
+        .unwrap()
+        .with_bias_correction(true);
+    let mut hll = Hll::new(settings);
+    for i in 0..5000 {
+        hll.add(&i);
+    }
+
+    let empty = hll.clone_empty();
+    assert!(matches!(empty, Hll::Empty(_)));
+    assert_eq!(0, empty.cardinality());
+    assert_eq!(&settings, empty.settings());
+    assert_eq!(vec![0x11, 0x8b, 0x7f], empty.to_bytes());
+    assert!(matches!(hll, Hll::Dense(_)));
+}
+
+#[test]
+fn test_to_bytes_ordered() {
+    let settings = Settings::new(11, 5, -1, true).unwrap();
+    let values = [5_u64, u64::MAX, 1 << 63, 1, 1 << 40];
+    let hll = Hll::from_iter_with_settings(settings, values);
+    assert!(matches!(hll, Hll::Explicit(_)));
+
+    let explicit_values = |bytes: &[u8]| -> Vec<u64> {
+        bytes[3..]
+            .chunks_exact(8)
+            .map(|chunk| u64::from_be_bytes(chunk.try_into().unwrap()))
+            .collect()
+    };
+
+    let signed = hll.to_bytes_ordered(ExplicitOrder::Signed);
+    assert_eq!(hll.to_bytes(), signed);
+    assert_eq!(
+        vec![1 << 63, u64::MAX, 1, 5, 1 << 40],
+        explicit_values(&signed)
+    );
+
+    let unsigned = hll.to_bytes_ordered(ExplicitOrder::Unsigned);
+    assert_eq!(
+        vec![1, 5, 1 << 40, 1 << 63, u64::MAX],
+        explicit_values(&unsigned)
+    );
+
+    assert_eq!(hll, Hll::from_bytes(&signed).unwrap());
+    assert_eq!(hll, Hll::from_bytes(&unsigned).unwrap());
+
+    // only explicit storage depends on the order
+    let mut dense = Hll::new_dense(settings).unwrap();
+    dense.add_raw_all(&values);
+    assert_eq!(
+        dense.to_bytes(),
+        dense.to_bytes_ordered(ExplicitOrder::Unsigned)
+    );
+}
+
+#[test]
+fn test_from_bytes_never_panics() {
+    fn check(buf: &[u8]) {
+        if let Ok(hll) = Hll::from_bytes(buf) {
+            hll.cardinality();
+            hll.to_bytes();
+        }
+        let _ = Hll::cardinality_from_bytes(buf);
+    }
+
+    // inputs found by the fuzz target that used to panic
+    let regressions = concat!(env!("CARGO_MANIFEST_DIR"), "/fuzz/regressions/from_bytes");
+    for entry in std::fs::read_dir(regressions).unwrap() {
+        check(&std::fs::read(entry.unwrap().path()).unwrap());
+    }
+
+    let mut rng = StdRng::seed_from_u64(0);
+    for i in 0..1000 {
+        let len = rng.random_range(0..64);
+        let mut buf: Vec<u8> = (0..len).map(|_| rng.random()).collect();
+
+        // most random headers fail on the version, so give half of them a valid one
+        if i % 2 == 0 && !buf.is_empty() {
+            buf[0] = 0x10 | rng.random_range(1..=4);
+        }
+        check(&buf);
+    }
+}
+
+// touch counts would allocate 2^31 u32s
+#[cfg(not(feature = "touch-counts"))]
+#[test]
+#[ignore = "allocates about 1.6 GB, run with cargo test -- --ignored"]
+fn test_max_log_2m_dense() {
+    // the bit address of the last register overflows u32 from a reg_width of 3.  wider registers
+    // take the same path but need gigabytes of memory.
+    let settings = Settings::new(31, 3, 0, false).unwrap();
+    let mut hll = Hll::new(settings).into_dense();
+
+    // the last register with a p(w) of 2
+    hll.add_raw((1 << 32) | ((1 << 31) - 1));
+
+    let bytes = hll.to_bytes();
+    drop(hll);
+    assert_eq!(settings.dense_serialized_len(), bytes.len());
+    assert_eq!(0b10, bytes[bytes.len() - 1]);
+    assert!(bytes[3..bytes.len() - 1].iter().all(|b| *b == 0));
+}
+
+#[test]
+fn test_sparse_widest_settings() {
+    // the widest settings whose sparse entries still fit in 32 bits
+    for (log_2m, reg_width) in [(31, 1), (26, 6), (24, 8)] {
+        let settings = SettingsBuilder::default()
+            .log_2m(log_2m)
+            .reg_width(reg_width)
+            .explicit_threshold(0)
+            .build()
+            .unwrap();
+        let mut hll = Hll::new(settings);
+        for i in 0..1000 {
+            hll.add(&i);
+        }
+        assert!(matches!(hll, Hll::Sparse(_)));
+
+        let bytes = hll.to_bytes();
+        assert_eq!(hll, Hll::from_bytes(&bytes).unwrap());
+        assert_eq!(bytes, Hll::from_bytes(&bytes).unwrap().to_bytes());
+    }
+}
+
+#[test]
+fn test_cardinality_extreme_settings() {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    // sparse storage keeps log_2m = 31 small enough to test
+    for (log_2m, reg_width, sparse_enabled) in [
+        (4, 1, false),
+        (4, 8, false),
+        (8, 1, false),
+        (31, 1, true),
+        (24, 8, true),
+    ] {
+        let settings = Settings::new(log_2m, reg_width, 0, sparse_enabled).unwrap();
+        let mut hll = Hll::new(settings);
+
+        // the hand off from the small range correction can dip, as it does in the java
+        // implementation, so the estimate is only expected to rise once no register is zero
+        let mut previous = 0;
+        for _ in 0..5000 {
+            hll.add_raw(rng.random());
+
+            let cardinality = hll.cardinality();
+            assert!(cardinality > 0 && cardinality < u64::MAX);
+
+            let num_of_zeros = match &hll {
+                Hll::Sparse(sparse_registers) => sparse_registers.indicator().1,
+                Hll::Dense(dense_registers) => dense_registers.indicator().1,
+                _ => 1,
+            };
+            if num_of_zeros == 0 {
+                assert!(
+                    cardinality >= previous,
+                    "log_2m {log_2m}, reg_width {reg_width}: {previous} -> {cardinality}"
+                );
+                previous = cardinality;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_raw_estimate() {
+    let settings = Settings::new(11, 5, 0, false).unwrap();
+    let mut hll = Hll::new(settings);
+    assert_eq!((2048.0, 2048), hll.indicator_values());
+
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..20_000 {
+        hll.add_raw(rng.random());
+    }
+
+    // well past the small range correction the raw estimate is what cardinality reports
+    let (sum, num_of_zeros) = hll.indicator_values();
+    assert_eq!(0, num_of_zeros);
+    assert_eq!(settings.alpha_msquared / sum, hll.raw_estimate());
+    assert_eq!(hll.cardinality(), hll.raw_estimate().ceil() as u64);
+
+    let error = (hll.raw_estimate() - 20_000.0).abs() / 20_000.0;
+    assert!(error < 3.0 * settings.expected_std_error());
+}
+
+#[test]
+fn test_cardinality_f64() {
+    let mut rng = StdRng::seed_from_u64(0);
+    for (explicit_threshold, sparse_enabled) in [(-1, true), (0, true), (0, false)] {
+        let mut hll = Hll::new(Settings::new(11, 5, explicit_threshold, sparse_enabled).unwrap());
+        assert_eq!(0.0, hll.cardinality_f64());
+
+        let mut added = 0;
+        for n in [1, 10, 100, 1_000, 10_000, 100_000] {
+            for _ in added..n {
+                hll.add_raw(rng.random());
+            }
+            added = n;
+
+            assert_eq!(hll.cardinality(), hll.cardinality_f64().ceil() as u64);
+        }
+    }
+
+    let mut hll = Hll::new(Settings::new(11, 5, -1, true).unwrap());
+    for i in 0..3 {
+        hll.add(&i);
+    }
+    assert_eq!(3.0, hll.cardinality_f64());
+}
+
+#[test]
+fn test_bytes_conversions() {
+    for (explicit_threshold, sparse_enabled) in [(-1, true), (0, true), (0, false)] {
+        let mut hll = Hll::new(Settings::new(11, 5, explicit_threshold, sparse_enabled).unwrap());
+        for i in 0..100 {
+            hll.add(&i);
+        }
+
+        let bytes: Vec<u8> = (&hll).into();
+        assert_eq!(hll.to_bytes(), bytes);
+        assert_eq!(hll, Hll::try_from(&bytes[..]).unwrap());
+
+        let owned: Vec<u8> = hll.clone().into();
+        assert_eq!(bytes, owned);
+    }
+
+    assert!(matches!(
+        Hll::try_from(&[0x11][..]),
+        Err(HllError::Truncated { .. })
+    ));
+}
+
+#[test]
+fn test_settings_accessors() {
+    for (log_2m, reg_width, explicit_threshold, sparse_enabled) in
+        [(11, 5, -1, true), (14, 6, 0, false), (10, 4, 128, true)]
+    {
+        let mut hll =
+            Hll::new(Settings::new(log_2m, reg_width, explicit_threshold, sparse_enabled).unwrap());
+        hll.add(&1);
+
+        let hll = Hll::from_bytes(&hll.to_bytes()).unwrap();
+        assert_eq!(log_2m, hll.precision());
+        assert_eq!(reg_width, hll.register_width());
+        assert_eq!(sparse_enabled, hll.sparse_enabled());
+
+        let expected_threshold = match explicit_threshold {
+            -1 => Settings::calculate_explicit_threshold(log_2m, reg_width),
+            threshold => threshold as u32,
+        };
+        assert_eq!(expected_threshold, hll.explicit_threshold());
+    }
+}
+
+#[test]
+fn test_diff_bytes() {
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for (explicit_threshold, sparse_enabled, count) in
+        [(0, false, 10_000), (0, true, 50), (-1, true, 20)]
+    {
+        let settings = Settings::new(11, 5, explicit_threshold, sparse_enabled).unwrap();
+        let mut baseline = Hll::new(settings);
+        for _ in 0..count {
+            baseline.add_raw(rng.random());
+        }
+
+        let mut updated = baseline.clone();
+        for _ in 0..count / 10 {
+            updated.add_raw(rng.random());
+        }
+
+        let diff = updated.diff_bytes(&baseline).unwrap();
+        if matches!(updated, Hll::Dense(_)) {
+            assert!(diff.len() < updated.to_bytes().len() / 4);
+        }
+
+        let mut replica = baseline.clone();
+        replica.apply_diff(&diff).unwrap();
+        assert!(updated.logically_eq(&replica));
+
+        let mut union = baseline.clone();
+        union.union(true, &updated).unwrap();
+        assert!(union.logically_eq(&replica));
+
+        // nothing changed since the baseline
+        let diff = updated.diff_bytes(&updated).unwrap();
+        assert_eq!(3, diff.len());
+    }
+
+    let baseline = Hll::new(Settings::new(12, 5, 0, false).unwrap());
+    let hll = Hll::new(Settings::new(11, 5, 0, false).unwrap());
+    assert!(matches!(
+        hll.diff_bytes(&baseline),
+        Err(HllError::Settings(SettingsError::MisMatch))
+    ));
+
+    let mut hll = hll;
+    assert!(matches!(
+        hll.apply_diff(&baseline.to_bytes()),
+        Err(HllError::Settings(SettingsError::MisMatch))
+    ));
+}
+
+#[test]
+fn test_register_newtypes() {
+    assert_eq!(RegisterIndex(7), RegisterIndex::from(7_u32));
+    assert_eq!(7, u32::from(RegisterIndex(7)));
+    assert_eq!(RegisterValue(3), RegisterValue::from(3_u8));
+    assert_eq!(3, u8::from(RegisterValue(3)));
+
+    let settings = Settings::new(11, 5, 0, true).unwrap();
+    let mut sparse = SparseRegisters::with_settings(&settings);
+    let (reg_num, value) = sparse.register_for(construct_hll_value(11, 7, 3)).unwrap();
+    assert_eq!((RegisterIndex(7), RegisterValue(3)), (reg_num, value));
+
+    assert!(sparse.set_if_greater(reg_num, value));
+    assert!(!sparse.set_if_greater(reg_num, RegisterValue(2)));
+    assert_eq!(Some(3), sparse.get(7));
+}
@@ -11,14 +11,14 @@ use std::{
 struct AddTestCase {
     hll: Hll,
     to_add: u64,
-    cardinality: u64,
+    cardinality: Option<u64>,
 }
 
 #[derive(Debug)]
 struct UnionTestCase {
     hll: Hll,
     to_union: Hll,
-    cardinality: u64,
+    cardinality: Option<u64>,
 }
 
 #[test]
@@ -58,9 +58,8 @@ fn integration_test(file: &fs::DirEntry, suite_dir: &Path) {
 
     if test_name.contains("_add_") {
         let mut hll = None;
-        let mut line_no = 2; // line 1 was discarded above
-
-        for line in lines {
+        // line 1 was discarded above
+        for (line_no, line) in (2..).zip(lines) {
             let line = line.expect("Failed to read line");
             let tt = parse_add_test_case(&line, line_no);
 
@@ -83,7 +82,7 @@ fn integration_test(file: &fs::DirEntry, suite_dir: &Path) {
 
                 // Check cardinality
                 assert_eq!(
-                    tt.cardinality,
+                    tt.cardinality.unwrap_or_else(|| saturated_cardinality(h)),
                     h.cardinality(),
                     "incorrect cardinality at line {} {test_name}, hll: \\x{}",
                     line_no,
@@ -98,14 +97,11 @@ fn integration_test(file: &fs::DirEntry, suite_dir: &Path) {
                     line_no
                 );
             }
-
-            line_no += 1;
         }
     } else {
         let mut hll = None;
-        let mut line_no = 2; // line 1 was discarded above
-
-        for line in lines {
+        // line 1 was discarded above
+        for (line_no, line) in (2..).zip(lines) {
             let line = line.expect("Failed to read line");
             let tt = parse_union_test_case(&line, line_no);
 
@@ -118,7 +114,7 @@ fn integration_test(file: &fs::DirEntry, suite_dir: &Path) {
 
                 // Check cardinality
                 assert_eq!(
-                    tt.cardinality,
+                    tt.cardinality.unwrap_or_else(|| saturated_cardinality(h)),
                     h.cardinality(),
                     "incorrect cardinality at line {} {test_name}, hll: \\x{}",
                     line_no,
@@ -135,12 +131,22 @@ fn integration_test(file: &fs::DirEntry, suite_dir: &Path) {
                     );
                 }
             }
-
-            line_no += 1;
         }
     }
 }
 
+/// parse_cardinality returns None for a NaN cardinality.  The reference implementations take the log
+/// of a negative number once the raw estimate passes 2^L, where this one saturates instead.
+fn parse_cardinality(cardinality: f64) -> Option<u64> {
+    (!cardinality.is_nan()).then(|| cardinality.ceil() as u64)
+}
+
+/// saturated_cardinality is the large range correction at its limit, just below 2^L.
+fn saturated_cardinality(hll: &Hll) -> u64 {
+    let two_to_l = hll.settings().two_to_l;
+    (-two_to_l * f64::EPSILON.ln()).ceil() as u64
+}
+
 fn parse_add_test_case(line: &str, line_no: usize) -> AddTestCase {
     let parts: Vec<&str> = line.split(',').collect();
     assert_eq!(parts.len(), 3, "required 3 columns at line {}", line_no);
@@ -155,7 +161,7 @@ fn parse_add_test_case(line: &str, line_no: usize) -> AddTestCase {
     AddTestCase {
         hll: parse_hll(parts[2], line_no),
         to_add: to_add as u64,
-        cardinality: cardinality.ceil() as u64,
+        cardinality: parse_cardinality(cardinality),
     }
 }
 
@@ -170,7 +176,7 @@ fn parse_union_test_case(line: &str, line_no: usize) -> UnionTestCase {
     UnionTestCase {
         hll: parse_hll(parts[3], line_no),
         to_union: parse_hll(parts[1], line_no),
-        cardinality: cardinality.ceil() as u64,
+        cardinality: parse_cardinality(cardinality),
     }
 }
 
@@ -189,8 +195,7 @@ fn parse_hll(hex_encoded: &str, line_no: usize) -> Hll {
     // Set sparse threshold to match Java implementation's test generator
     // This is required for tests to match up with outputs from Java library
 
-    let mut settings = *hll.settings();
-    settings.sparse_threshold = Some(850);
+    let settings = hll.settings().with_sparse_threshold(850).unwrap();
 
     hll.clone_with_settings(&settings)
 }
@@ -1,21 +1,94 @@
-use dense::DenseRegisters;
-use explicit::ExplicitStorage;
-use settings::{Settings, SettingsError};
-use sparse::SparseRegisters;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("no_std builds require the `libm` feature");
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+pub use builder::{HllBuilder, SettingsBuilder};
+use core::fmt;
+use core::hash::{BuildHasher, BuildHasherDefault, Hash};
+use core::ops::{BitOr, BitOrAssign};
+pub use count::count_distinct;
+pub use dense::{DenseRegisters, DenseRegistersRef};
+pub use explicit::{ExplicitOrder, ExplicitStorage};
+pub use hash::{Murmur3Hasher, murmur3_64};
+pub use pg::PgHllError;
+pub use redis::RedisHllError;
+pub use settings::{Settings, SettingsError, SettingsPreset};
+pub use simulate::simulate_error;
+#[cfg(feature = "std")]
+pub use sink::HllSink;
+pub use sparse::SparseRegisters;
 use thiserror::Error;
+use utils::crc32;
 
+mod bias;
+mod bias_data;
+mod builder;
+mod count;
 mod dense;
 #[cfg(test)]
 mod dense_test;
+mod diagnostics;
 mod explicit;
+mod hash;
+#[cfg(test)]
+mod hll_test;
 #[cfg(test)]
 mod integration_test;
+mod math;
+#[cfg(feature = "rayon")]
+mod par;
+mod pg;
+mod redis;
+#[cfg(feature = "serde")]
+mod serde_impl;
 mod settings;
+mod simulate;
+#[cfg(feature = "std")]
+mod sink;
 mod sparse;
 #[cfg(test)]
 mod sparse_test;
 mod utils;
 
+/// RegisterIndex is the number of a register, from 0 to 2^log_2m - 1.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RegisterIndex(pub u32);
+
+/// RegisterValue is the value held by a register, the largest p(w) of the hashes it has seen.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RegisterValue(pub u8);
+
+impl From<u32> for RegisterIndex {
+    fn from(reg_num: u32) -> Self {
+        RegisterIndex(reg_num)
+    }
+}
+
+impl From<RegisterIndex> for u32 {
+    fn from(reg_num: RegisterIndex) -> Self {
+        reg_num.0
+    }
+}
+
+impl From<u8> for RegisterValue {
+    fn from(value: u8) -> Self {
+        RegisterValue(value)
+    }
+}
+
+impl From<RegisterValue> for u8 {
+    fn from(value: RegisterValue) -> Self {
+        value.0
+    }
+}
+
 /// `Register` is an add-on interface to storage that is implemented by the probabalistic types.
 trait Registers {
     fn log_2m(&self) -> u32;
@@ -23,8 +96,8 @@ trait Registers {
     fn m_bits_mask(&self) -> u64;
 
     /// set_if_greater sets the register value of register reg_num to the provided value if and only if it's greater than
-    /// the current value.
-    fn set_if_greater(&mut self, reg_num: u32, value: u8);
+    /// the current value. Returns whether the register was raised.
+    fn set_if_greater(&mut self, reg_num: RegisterIndex, value: RegisterValue) -> bool;
 
     /// indicator computes the "indicator function" (Z in the HLL paper).  It additionally returns the number of
     /// registers whose value is zero (V in the paper).  The returned values are used to drive cardinality calculations.
@@ -32,9 +105,24 @@ trait Registers {
     /// For reference, Z = indicator(2^(-M[j])) for all j from 0 -> num registers where M[j] is the register value.
     fn indicator(&self) -> (f64, u32);
 
-    /// calculates the register and value to use when calling `set_if_greater`. Returns None if
-    /// value is 0.
-    fn set(&mut self, value: u64) {
+    /// record_touch is called with the target register of every `set` call. It is a no-op unless the
+    /// storage collects diagnostics (see the `touch-counts` feature).
+    fn record_touch(&mut self, _reg_num: RegisterIndex) {}
+
+    /// calculates the register and value to use when calling `set_if_greater`. Returns whether a
+    /// register was raised, which is never the case if value is 0.
+    fn set(&mut self, value: u64) -> bool {
+        let Some((reg_num, p_w)) = self.register_for(value) else {
+            return false;
+        };
+        self.record_touch(reg_num);
+
+        self.set_if_greater(reg_num, p_w)
+    }
+
+    /// register_for returns the register number and register value that `set` uses for value, or
+    /// None if value doesn't set a register.
+    fn register_for(&self, value: u64) -> Option<(RegisterIndex, RegisterValue)> {
         // following documentation courtesy of the java implementation:
         //
         // p(w): position of the least significant set bit (one-indexed)
@@ -51,24 +139,26 @@ trait Registers {
             // 0 is the original initialization value of the registers, so by
             // doing this the multiset simply ignores it. This is acceptable
             // because the probability is 1/(2^(2^registerSizeInBits)).
-            return;
+            return None;
         }
 
         // NOTE : trailing zeros == the 0-based index of the least significant 1
         //        bit.
         let p_w = (1 + (substream_value | self.pw_max_mask()).trailing_zeros()) as u8;
         // NOTE:  no +1 as in paper since 0-based indexing
+        // this is safe because the m_bits_mask is 1 less bit in length than log_2m bits
         let i = value & self.m_bits_mask();
 
-        // this is safe because the m_bits_mask is 1 less bit in length than log_2m bits
-        self.set_if_greater(i as u32, p_w);
+        Some((RegisterIndex(i as u32), RegisterValue(p_w)))
     }
 }
 
 pub trait Storage {
     fn bytes_size(&self) -> usize;
     fn to_bytes(&self, buf: &mut [u8]);
-    fn from_bytes(settings: &Settings, buf: &[u8]) -> Self;
+    fn from_bytes(settings: &Settings, buf: &[u8]) -> Result<Self, HllError>
+    where
+        Self: Sized;
     fn clear(&mut self);
 }
 
@@ -78,6 +168,72 @@ pub enum HllError {
     Settings(#[from] SettingsError),
     #[error("invalid version {0}")]
     Version(u8),
+    #[error("invalid storage type {0}")]
+    InvalidTypeId(u8),
+    #[error("register {0} is out of range")]
+    RegisterIndex(u32),
+    #[error("register value {0} is out of range")]
+    RegisterValue(u8),
+    #[error("expected at least {expected} bytes, found {found}")]
+    Truncated { expected: usize, found: usize },
+    #[error("{len} bytes is not a valid storage length for type {type_id}")]
+    StorageLength { type_id: u8, len: usize },
+    #[error("type {0} storage doesn't record its length")]
+    UnknownLength(u8),
+    #[error("sparse storage is disabled")]
+    SparseDisabled,
+    #[error("more registers are set than the sparse threshold of {0}")]
+    SparseThreshold(i32),
+    #[error("sparse entries of {0} bits don't fit in 32 bits")]
+    SparseEntryWidth(u32),
+    #[error("checksum mismatch, expected {expected:#010x} but found {found:#010x}")]
+    Checksum { expected: u32, found: u32 },
+    #[error("invalid redis hll: {0}")]
+    Redis(#[from] RedisHllError),
+}
+
+/// HEADER_LEN is the size of the version/type byte followed by the serialized settings.
+const HEADER_LEN: usize = 3;
+
+/// SERIALIZATION_VERSION is the storage spec version written in the top nibble of the first byte.
+const SERIALIZATION_VERSION: u8 = 1;
+
+/// CHECKSUM_FLAG is set in the otherwise unused top bit of the cutoff byte when a CRC32 of the
+/// preceding bytes is appended to the serialized Hll.
+const CHECKSUM_FLAG: u8 = 1 << 7;
+
+/// CHECKSUM_LEN is the size of the big endian CRC32 written by `Hll::to_bytes_with_checksum`.
+const CHECKSUM_LEN: usize = 4;
+
+/// strip_checksum verifies and removes the trailing checksum if the header says there is one.  buf
+/// must hold at least the header.
+fn strip_checksum(buf: &[u8]) -> Result<&[u8], HllError> {
+    if buf[2] & CHECKSUM_FLAG == 0 {
+        return Ok(buf);
+    }
+
+    check_len(buf, HEADER_LEN + CHECKSUM_LEN)?;
+    let (buf, checksum) = buf.split_at(buf.len() - CHECKSUM_LEN);
+
+    let expected = u32::from_be_bytes(checksum.try_into().unwrap());
+    let found = crc32(buf);
+    if expected != found {
+        return Err(HllError::Checksum { expected, found });
+    }
+
+    Ok(buf)
+}
+
+/// check_len returns `HllError::Truncated` if buf is shorter than expected.
+fn check_len(buf: &[u8], expected: usize) -> Result<(), HllError> {
+    if buf.len() < expected {
+        return Err(HllError::Truncated {
+            expected,
+            found: buf.len(),
+        });
+    }
+
+    Ok(())
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -93,20 +249,115 @@ impl Hll {
         Hll::Empty(settings)
     }
 
-    pub(crate) fn add_raw(&mut self, value: u64) {
+    /// new_dense returns an Hll that already has its Dense storage allocated, so the allocation
+    /// doesn't happen on the first add.  Values always go straight to the registers, skipping
+    /// Explicit and Sparse storage.
+    pub fn new_dense(settings: Settings) -> Result<Self, HllError> {
+        Ok(Hll::Empty(settings).into_dense())
+    }
+
+    /// new_sparse returns an Hll that starts out with Sparse storage, skipping Explicit storage.
+    /// It fails if the settings disable sparse storage.
+    pub fn new_sparse(settings: Settings) -> Result<Self, HllError> {
+        Hll::Empty(settings).into_sparse()
+    }
+
+    /// with_params builds the settings (see `Settings::new`) and an empty Hll in one step.
+    pub fn with_params(
+        log_2m: u32,
+        reg_width: u32,
+        explicit_threshold: i32,
+        sparse_enabled: bool,
+    ) -> Result<Self, SettingsError> {
+        let settings = Settings::new(log_2m, reg_width, explicit_threshold, sparse_enabled)?;
+
+        Ok(Self::new(settings))
+    }
+
+    /// from_register_map builds an Hll from known register maxima. Zero valued registers are
+    /// ignored. The result is Sparse if enabled and the register count is within the sparse
+    /// threshold, otherwise Dense.  Values above 64 - log_2m, the largest p(w) the hash bits above
+    /// the register index allow, fail with `HllError::RegisterValue` like they do in `from_bytes`.
+    pub fn from_register_map(
+        settings: Settings,
+        regs: &BTreeMap<u32, u8>,
+    ) -> Result<Self, HllError> {
+        let reg_cnt: u64 = 1 << settings.log_2m;
+        let max_value = settings.max_hashed_register_value();
+
+        for (reg_num, value) in regs.iter() {
+            if *reg_num as u64 >= reg_cnt {
+                return Err(HllError::RegisterIndex(*reg_num));
+            }
+
+            if *value > max_value {
+                return Err(HllError::RegisterValue(*value));
+            }
+        }
+
+        let regs = regs.iter().filter(|(_, value)| **value != 0);
+
+        match settings.sparse_threshold {
+            Some(sparse_threshold) if regs.clone().count() as i32 <= sparse_threshold => {
+                let mut registers = SparseRegisters::with_settings(&settings);
+                registers.buf.extend(regs.map(|(k, v)| (*k, *v)));
+
+                Ok(Hll::Sparse(registers))
+            }
+            _ => {
+                let mut registers = DenseRegisters::with_settings(&settings);
+                for (reg_num, value) in regs {
+                    registers.set_reg(*reg_num, *value);
+                }
+
+                Ok(Hll::Dense(registers))
+            }
+        }
+    }
+
+    /// add hashes the value with `Murmur3Hasher` (MurmurHash3 x64 128, seed 0, lower 64 bits) and
+    /// adds the result.  Integers hash the same as the postgres `hll_hash_*` functions but other
+    /// `Hash` implementations add framing bytes, use `add_bytes` when strings or bytes need to
+    /// match other implementations.
+    pub fn add<T: Hash + ?Sized>(&mut self, value: &T) {
+        self.add_with_hasher(value, &BuildHasherDefault::<Murmur3Hasher>::default());
+    }
+
+    /// add_with_hasher hashes the value with a hasher built by build_hasher and adds the result.
+    /// The hash determines which registers are set, so Hlls filled with different hashers, or a
+    /// hasher that is seeded differently, can't be meaningfully unioned or compared.  Data sets
+    /// shared with other implementations need to use the hash those implementations use.
+    pub fn add_with_hasher<T: Hash + ?Sized, S: BuildHasher>(
+        &mut self,
+        value: &T,
+        build_hasher: &S,
+    ) {
+        self.add_raw(build_hasher.hash_one(value));
+    }
+
+    /// add_bytes hashes the raw bytes with `murmur3_64` and adds the result.  This matches
+    /// `hll_hash_bytes` and `hll_hash_text` from the postgres extension.
+    pub fn add_bytes(&mut self, bytes: &[u8]) {
+        self.add_raw(murmur3_64(bytes));
+    }
+
+    /// add_raw adds an already hashed value.  The hash should be 64 well distributed bits, such as
+    /// the output of `murmur3_64`.  A value of 0 carries no information and is ignored.
+    ///
+    /// ```
+    /// use rust_hll::{Hll, Settings, murmur3_64};
+    ///
+    /// let mut hll = Hll::new(Settings::new(11, 5, -1, true).unwrap());
+    /// hll.add_raw(murmur3_64(b"hello"));
+    /// assert_eq!(1, hll.cardinality());
+    /// ```
+    pub fn add_raw(&mut self, value: u64) {
         if value == 0 {
             return;
         }
 
         if let Hll::Empty(settings) = self {
-            if settings.explicit_threshold() > 0 {
-                *self = Hll::Explicit(ExplicitStorage::with_settings(settings));
-            } else if settings.sparse_threshold.is_some() {
-                let registers = SparseRegisters::with_settings(settings);
-                *self = Hll::Sparse(registers);
-            } else {
-                *self = Hll::Dense(DenseRegisters::with_settings(settings));
-            }
+            *self = Self::initial_storage(settings);
         }
 
         match self {
@@ -130,13 +381,163 @@ impl Hll {
         }
     }
 
+    /// from_iter_with_settings builds an Hll from already hashed values.
+    pub fn from_iter_with_settings<I: IntoIterator<Item = u64>>(
+        settings: Settings,
+        values: I,
+    ) -> Self {
+        let mut hll = Hll::new(settings);
+        for value in values {
+            hll.add_raw(value);
+        }
+
+        hll
+    }
+
+    /// add_raw_all adds each of the provided hashed values. Once the Hll is Dense the remaining
+    /// values are written straight to the registers without re-checking the storage type.
+    pub fn add_raw_all(&mut self, values: &[u64]) {
+        let mut values = values.iter();
+
+        while !matches!(self, Hll::Dense(_)) {
+            match values.next() {
+                Some(value) => self.add_raw(*value),
+                None => return,
+            }
+        }
+
+        if let Hll::Dense(dense_registers) = self {
+            for value in values {
+                dense_registers.set(*value);
+            }
+        }
+    }
+
+    /// add_all_bytes hashes each of the provided byte slices with `murmur3_64`, the same as
+    /// `add_bytes`, and adds the results.  Like `add_raw_all`, once the Hll is Dense the remaining
+    /// values are written straight to the registers without re-checking the storage type.
+    pub fn add_all_bytes<I>(&mut self, items: I)
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let mut items = items.into_iter();
+
+        while !matches!(self, Hll::Dense(_)) {
+            match items.next() {
+                Some(item) => self.add_bytes(item.as_ref()),
+                None => return,
+            }
+        }
+
+        if let Hll::Dense(dense_registers) = self {
+            for item in items {
+                dense_registers.set(murmur3_64(item.as_ref()));
+            }
+        }
+    }
+
+    /// diff_bytes serializes the registers that are higher in `self` than in `baseline`, so a replica
+    /// holding `baseline` can catch up with `apply_diff` without receiving the whole Hll.  The diff
+    /// is an Hll with Sparse storage of just those registers.  Explicit values are compared by the
+    /// registers they set, so the diff of an Explicit Hll is also registers.  Both Hlls must have
+    /// the same log_2m and reg_width, and their sparse entries must fit in 32 bits.
+    pub fn diff_bytes(&self, baseline: &Self) -> Result<Vec<u8>, HllError> {
+        self.settings_check(baseline)?;
+
+        let settings = self.settings();
+        let entry_width = settings.log_2m + settings.reg_width;
+        if entry_width > u32::BITS {
+            return Err(HllError::SparseEntryWidth(entry_width));
+        }
+
+        // both sides yield registers in order, so the baseline is walked alongside once
+        let mut baseline_registers = baseline.registers().peekable();
+        let mut diff = SparseRegisters::with_settings(settings);
+        for (reg_num, value) in self.registers() {
+            let mut baseline_value = 0;
+            while let Some((baseline_reg_num, value)) =
+                baseline_registers.next_if(|(baseline_reg_num, _)| *baseline_reg_num <= reg_num)
+            {
+                if baseline_reg_num == reg_num {
+                    baseline_value = value;
+                }
+            }
+
+            if value > baseline_value {
+                diff.set_if_greater(RegisterIndex(reg_num), RegisterValue(value));
+            }
+        }
+
+        Ok(Hll::Sparse(diff).to_bytes())
+    }
+
+    /// apply_diff raises the registers in a diff written by `diff_bytes`.  Applying the diff to the
+    /// baseline it was taken against yields the union of the baseline and the Hll it was taken
+    /// from.  An Explicit Hll is converted to registers.  The diff must have the same log_2m and
+    /// reg_width.
+    pub fn apply_diff(&mut self, diff: &[u8]) -> Result<(), HllError> {
+        let diff = Hll::from_bytes(diff)?;
+        self.settings_check(&diff)?;
+
+        if let Hll::Explicit(explicit_storage) = self {
+            *self = explicit_storage.as_registers();
+        }
+
+        // the diff was read with the settings in its header, which may not include overrides
+        self.union(true, &diff.clone_with_settings(self.settings()))
+    }
+
+    /// initial_storage returns the empty storage an Hll with the provided settings starts out with
+    /// once the first value is added.
+    fn initial_storage(settings: &Settings) -> Self {
+        if settings.explicit_threshold() > 0 {
+            Hll::Explicit(ExplicitStorage::with_settings(settings))
+        } else if settings.sparse_threshold.is_some() {
+            Hll::Sparse(SparseRegisters::with_settings(settings))
+        } else {
+            Hll::Dense(DenseRegisters::with_settings(settings))
+        }
+    }
+
     pub fn union(&mut self, strict: bool, other: &Self) -> Result<(), HllError> {
+        self.union_counting(strict, other).map(|_| ())
+    }
+
+    /// union_counting is like `union` but returns how many registers were raised by the merge. While
+    /// `self` stays Explicit the count is the number of newly added values instead. This makes it
+    /// possible to detect when repeated merges stop changing anything.
+    ///
+    /// Non-strict unions also accept an `other` with a narrower reg_width than `self`.  Its register
+    /// values are valid in the wider register space and are merged without truncation.
+    pub fn union_counting(&mut self, strict: bool, other: &Self) -> Result<u32, HllError> {
         if strict {
             self.settings_check(other)?;
         }
 
-        match self {
+        let changed = match self {
+            // other can't simply be cloned since its registers don't share our layout
+            Hll::Empty(settings)
+                if !matches!(other, Hll::Empty(_))
+                    && settings.reg_width != other.settings().reg_width =>
+            {
+                let mut new_storage = Self::initial_storage(settings);
+                let changed = new_storage.union_counting(strict, other)?;
+
+                *self = new_storage;
+                changed
+            }
             Hll::Empty(settings) => {
+                let changed = match other {
+                    Hll::Empty(_settings) => 0,
+                    Hll::Explicit(explicit_storage) => explicit_storage.len() as u32,
+                    Hll::Sparse(sparse_registers) => sparse_registers.len() as u32,
+                    Hll::Dense(dense_registers) => dense_registers
+                        .iter()
+                        .filter(|(_, value)| *value != 0)
+                        .count() as u32,
+                };
+
                 *self = match &other {
                     Hll::Sparse(sparse_registers) => match settings.sparse_threshold {
                         Some(sparse_threshold) => {
@@ -150,96 +551,447 @@ impl Hll {
                     },
                     _ => other.clone(),
                 };
+
+                changed
             }
             Hll::Explicit(lhs) => match other {
-                Hll::Empty(_settings) => {}
-                Hll::Explicit(rhs) => {
-                    lhs.union_explicit(rhs);
-                }
+                Hll::Empty(_settings) => 0,
+                Hll::Explicit(rhs) => lhs.union_explicit(rhs),
                 Hll::Sparse(_sparse_registers) => {
                     let mut new_storage = lhs.as_registers();
-                    new_storage.union(strict, other)?;
+                    let changed = new_storage.union_counting(strict, other)?;
 
                     *self = new_storage;
+                    changed
                 }
                 Hll::Dense(_dense_registers) => {
                     let mut new_storage = lhs.as_registers();
-                    new_storage.union(strict, other)?;
+                    let changed = new_storage.union_counting(strict, other)?;
 
                     *self = new_storage;
+                    changed
                 }
             },
             Hll::Sparse(sparse_registers) => match other {
-                Hll::Empty(_settings) => {}
+                Hll::Empty(_settings) => 0,
                 Hll::Explicit(explicit_storage) => {
-                    sparse_registers.union_explicit(explicit_storage);
+                    sparse_registers.union_explicit(explicit_storage)
                 }
                 Hll::Sparse(rhs_sparse_registers) => {
-                    sparse_registers.union_sparse(rhs_sparse_registers);
+                    sparse_registers.union_sparse(rhs_sparse_registers)
                 }
                 Hll::Dense(dense_registers) => {
                     let mut new_storage = sparse_registers.to_dense(None);
-                    new_storage.union_dense(dense_registers);
+                    let changed = new_storage.union_dense(dense_registers)?;
 
                     *self = Hll::Dense(new_storage);
+                    changed
                 }
             },
             Hll::Dense(dense_registers) => match other {
-                Hll::Empty(_settings) => {}
-                Hll::Explicit(explicit_storage) => {
-                    dense_registers.union_explicit(explicit_storage);
-                }
-                Hll::Sparse(sparse_registers) => {
-                    dense_registers.union_sparse(sparse_registers);
-                }
+                Hll::Empty(_settings) => 0,
+                Hll::Explicit(explicit_storage) => dense_registers.union_explicit(explicit_storage),
+                Hll::Sparse(sparse_registers) => dense_registers.union_sparse(sparse_registers),
                 Hll::Dense(rhs_dense_registers) => {
-                    dense_registers.union_dense(rhs_dense_registers);
+                    dense_registers.union_dense(rhs_dense_registers)?
                 }
             },
-        }
+        };
 
         if self.is_full() {
             self.upgrade();
         }
 
+        Ok(changed)
+    }
+
+    /// union_changed is like `union` but returns whether self changed, either because a register
+    /// was raised, a value was added to Explicit storage, or the storage type changed.  A false
+    /// result means self is exactly as it was, which is useful for cache invalidation.
+    pub fn union_changed(&mut self, strict: bool, other: &Self) -> Result<bool, HllError> {
+        let type_id = self.type_id();
+        let changed = self.union_counting(strict, other)?;
+
+        Ok(changed > 0 || self.type_id() != type_id)
+    }
+
+    /// union_many unions each of others into this Hll.  When any of them is Dense the result will be
+    /// too, so this Hll is upgraded to Dense once and every input is merged straight into its
+    /// registers instead of moving through the intermediate representations.  Every input is
+    /// checked before anything is merged, so nothing is merged if any of the inputs can't be.
+    pub fn union_many(&mut self, strict: bool, others: &[Hll]) -> Result<(), HllError> {
+        let settings = *self.settings();
+        for other in others {
+            if strict {
+                self.settings_check(other)?;
+            }
+            if let Hll::Dense(dense_registers) = other {
+                settings.widening_check(&dense_registers.settings)?;
+            }
+        }
+
+        if !others.iter().any(|other| matches!(other, Hll::Dense(_))) {
+            // only unions with Dense storage can fail once the settings are checked
+            for other in others {
+                self.union(strict, other)?;
+            }
+
+            return Ok(());
+        }
+
+        let mut registers = match core::mem::replace(self, Hll::Empty(settings)) {
+            Hll::Empty(_) => DenseRegisters::with_settings(&settings),
+            Hll::Explicit(explicit_storage) => {
+                let mut registers = DenseRegisters::with_settings(&settings);
+                registers.union_explicit(&explicit_storage);
+                registers
+            }
+            Hll::Sparse(sparse_registers) => sparse_registers.to_dense(None),
+            Hll::Dense(dense_registers) => dense_registers,
+        };
+
+        for other in others {
+            match other {
+                Hll::Empty(_) => {}
+                Hll::Explicit(explicit_storage) => {
+                    registers.union_explicit(explicit_storage);
+                }
+                Hll::Sparse(sparse_registers) => {
+                    registers.union_sparse(sparse_registers);
+                }
+                Hll::Dense(dense_registers) => {
+                    registers.union_dense(dense_registers)?;
+                }
+            }
+        }
+
+        *self = Hll::Dense(registers);
         Ok(())
     }
 
+    /// union_bytes unions a serialized Hll into this one without the caller having to deserialize it
+    /// first.
+    pub fn union_bytes(&mut self, strict: bool, bytes: &[u8]) -> Result<(), HllError> {
+        let other = Self::from_bytes(bytes)?;
+        self.union(strict, &other)
+    }
+
+    /// union_downsample merges an Hll with more registers into this one.  Each register of other
+    /// is folded into the register sharing its low log_2m bits, as if its hash had been added
+    /// with our log_2m: the remaining index bits become the low bits of the hashed value.  The
+    /// result only has the accuracy of the smaller Hll.
+    ///
+    /// Downsampling only works in one direction, other can't have fewer registers than self.  Hlls
+    /// with the same number of registers are merged with a non-strict `union`.
+    pub fn union_downsample(&mut self, other: &Self) -> Result<(), HllError> {
+        let settings = *self.settings();
+        let other_log_2m = other.settings().log_2m;
+        if other_log_2m < settings.log_2m {
+            return Err(SettingsError::MisMatch.into());
+        }
+
+        if other_log_2m == settings.log_2m {
+            return self.union(false, other);
+        }
+
+        // explicit values are the hashes themselves so they can be added exactly
+        if let Hll::Explicit(explicit_storage) = other {
+            explicit_storage
+                .iter()
+                .for_each(|value| self.add_raw(value));
+            return Ok(());
+        }
+
+        let shift = other_log_2m - settings.log_2m;
+        let max_value = settings.max_hashed_register_value();
+        let mut registers: BTreeMap<u32, u8> = BTreeMap::new();
+        for (reg_num, value) in other.nonzero_registers() {
+            let reg_num_high = reg_num >> settings.log_2m;
+            let value = match reg_num_high {
+                0 => value as u32 + shift,
+                _ => reg_num_high.trailing_zeros() + 1,
+            };
+
+            let register = registers
+                .entry(reg_num & settings.m_bits_mask as u32)
+                .or_default();
+            *register = (*register).max(value.min(max_value as u32) as u8);
+        }
+
+        self.union(true, &Self::from_register_map(settings, &registers)?)
+    }
+
+    /// union_cardinality returns the cardinality of the union of the two Hlls without modifying
+    /// either.  The non-dense operand is cloned and unioned with the other, so the settings must
+    /// match as for a strict `union`.
+    pub fn union_cardinality(&self, other: &Self) -> Result<u64, HllError> {
+        self.settings_check(other)?;
+
+        let (base, other) = match (self, other) {
+            (Hll::Dense(_), other) if !matches!(other, Hll::Dense(_)) => (other, self),
+            _ => (self, other),
+        };
+
+        let mut union = base.clone();
+        union.union(true, other)?;
+
+        Ok(union.cardinality())
+    }
+
+    /// intersect_cardinality estimates the number of distinct values in both Hlls using
+    /// inclusion-exclusion: |A| + |B| - |A ∪ B|.  Negative estimates are clamped to 0.
+    ///
+    /// The error of each of the three estimates is relative to its own cardinality, so the error of
+    /// the result is relative to the union.  Intersections that are small compared to either set,
+    /// such as when the sets are very different in size, can be dominated by that error.
+    pub fn intersect_cardinality(&self, other: &Self) -> Result<u64, HllError> {
+        let union = self.union_cardinality(other)?;
+
+        Ok(self
+            .cardinality()
+            .saturating_add(other.cardinality())
+            .saturating_sub(union))
+    }
+
+    /// jaccard estimates the Jaccard similarity |A ∩ B| / |A ∪ B| of the two Hlls, between 0.0 and
+    /// 1.0.  Two empty Hlls have a similarity of 0.0.  The intersection is estimated with
+    /// inclusion-exclusion, see `intersect_cardinality` for its accuracy.
+    pub fn jaccard(&self, other: &Self) -> Result<f64, HllError> {
+        let union = self.union_cardinality(other)?;
+        if union == 0 {
+            return Ok(0.0);
+        }
+
+        let intersection = self
+            .cardinality()
+            .saturating_add(other.cardinality())
+            .saturating_sub(union);
+
+        Ok((intersection as f64 / union as f64).min(1.0))
+    }
+
+    /// cardinality returns the estimated number of distinct values added to the Hll. Estimates that
+    /// do not fit in a u64 saturate to `u64::MAX`. Use `cardinality_checked` to detect this.  Of the
+    /// values the hash can produce, only a reg_width of 5 with a log_2m of 29 or more gets there, as
+    /// the large range correction approaches 2^L.
     pub fn cardinality(&self) -> u64 {
+        math::ceil(self.cardinality_f64()) as u64
+    }
+
+    /// cardinality_f64 returns the estimated cardinality before `cardinality` rounds it up, which
+    /// keeps the fractional part when averaging estimates or feeding them into further math.
+    /// Explicit storage returns its exact count.
+    pub fn cardinality_f64(&self) -> f64 {
+        self.estimate_cardinality()
+    }
+
+    /// cardinality_with_error returns the estimated cardinality along with its standard relative
+    /// error, 1.04 / sqrt(m) where m is the number of registers.  This is the asymptotic standard
+    /// error of the estimator rather than a bound; roughly two thirds of estimates fall within it.
+    /// Empty and Explicit storage count exactly and report an error of 0.
+    pub fn cardinality_with_error(&self) -> (u64, f64) {
+        let error = match self {
+            Hll::Empty(_) | Hll::Explicit(_) => 0.0,
+            Hll::Sparse(_) | Hll::Dense(_) => self.settings().expected_std_error(),
+        };
+
+        (self.cardinality(), error)
+    }
+
+    /// relative_error_estimate returns the expected relative standard error of `cardinality` given
+    /// the estimator regime the Hll is in.  It is a heuristic built from the textbook error of each
+    /// estimator:
+    ///
+    /// - Explicit and Empty storage are exact and return 0.0.
+    /// - While the small range correction (linear counting) is used, the error of linear counting,
+    ///   sqrt(m * (e^t - t - 1)) / n with t = n / m, is returned if it is larger than the standard
+    ///   error.  It grows past the standard error as the registers fill up towards the cutoff.
+    /// - In the normal range the standard error, 1.04 / sqrt(m), is returned.
+    /// - While the large range correction is used, the standard error is scaled by how much the
+    ///   correction amplifies errors in the raw estimate.  This is infinite once the raw estimate
+    ///   reaches 2^L and the correction is undefined.
+    ///
+    /// Estimates from the HyperLogLog++ bias correction use the standard error.
+    pub fn relative_error_estimate(&self) -> f64 {
         let (sum, num_of_zeros) = match self {
-            Hll::Empty(_) => return 0,
-            Hll::Explicit(explicit_storage) => return explicit_storage.len(),
+            Hll::Empty(_) | Hll::Explicit(_) => return 0.0,
             Hll::Sparse(sparse_registers) => sparse_registers.indicator(),
             Hll::Dense(dense_registers) => dense_registers.indicator(),
         };
 
         let settings = self.settings();
+        let std_error = settings.expected_std_error();
+        let estimator = settings.alpha_msquared / sum;
+
+        if settings.bias_correction && bias::estimate(settings, estimator, num_of_zeros).is_some() {
+            return std_error;
+        }
+
+        if (num_of_zeros != 0) && (estimator < settings.small_estimator_cutoff) {
+            let m = settings.registers() as f64;
+            let load = math::ln(m / num_of_zeros as f64);
+            if load == 0.0 {
+                return std_error;
+            }
+
+            let linear_counting_error = math::sqrt(m * (math::exp(load) - load - 1.0)) / (m * load);
+            return std_error.max(linear_counting_error);
+        }
+
+        if estimator <= settings.large_estimator_cutoff {
+            return std_error;
+        }
+
+        let x = estimator / settings.two_to_l;
+        if x >= 1.0 {
+            return f64::INFINITY;
+        }
+
+        std_error * x / ((1.0 - x) * -math::ln(1.0 - x))
+    }
+
+    /// cardinality_checked is like `cardinality` but returns None instead of saturating when the
+    /// estimate does not fit in a u64.
+    pub fn cardinality_checked(&self) -> Option<u64> {
+        let estimate = math::ceil(self.estimate_cardinality());
+
+        // u64::MAX as f64 rounds up to 2^64, which is itself out of range
+        if !(0.0..(u64::MAX as f64)).contains(&estimate) {
+            return None;
+        }
+
+        Some(estimate as u64)
+    }
+
+    /// cardinality_deterministic is like `cardinality` but rounds the estimate to 6 decimal places
+    /// before taking the ceiling. `ln`/`powf` may differ in the last ulp across platforms which is
+    /// enough to flip the ceiling of an estimate that lands on an integer, so this should be used
+    /// when the same sketch must produce the same count on every machine.
+    pub fn cardinality_deterministic(&self) -> u64 {
+        const PRECISION: f64 = 1e6;
+
+        math::ceil(math::round(self.estimate_cardinality() * PRECISION) / PRECISION) as u64
+    }
+
+    /// indicator_values returns the indicator function Z, the sum of 2^-register over every
+    /// register, and the number of zero registers V that `cardinality` is estimated from.  Explicit
+    /// storage reports the registers its values would set and Empty storage has every register at 0.
+    pub fn indicator_values(&self) -> (f64, u32) {
+        match self {
+            Hll::Empty(settings) => (settings.registers() as f64, settings.registers()),
+            Hll::Explicit(explicit_storage) => explicit_storage.as_registers().indicator_values(),
+            Hll::Sparse(sparse_registers) => sparse_registers.indicator(),
+            Hll::Dense(dense_registers) => dense_registers.indicator(),
+        }
+    }
+
+    /// raw_estimate returns the uncorrected HyperLogLog estimate, alpha * m^2 / Z, without the
+    /// small range, large range or bias corrections `cardinality` applies.  Together with
+    /// `indicator_values` this allows experimenting with other corrections.
+    pub fn raw_estimate(&self) -> f64 {
+        let (sum, _) = self.indicator_values();
+        self.settings().alpha_msquared / sum
+    }
 
+    /// len returns the estimated cardinality as a usize for interop with count oriented APIs.  It
+    /// saturates to `usize::MAX` if the estimate does not fit.
+    pub fn len(&self) -> usize {
+        self.cardinality().try_into().unwrap_or(usize::MAX)
+    }
+
+    /// heap_size returns the number of bytes allocated by the storage.
+    pub fn heap_size(&self) -> usize {
+        match self {
+            Hll::Empty(_) => 0,
+            Hll::Explicit(explicit_storage) => explicit_storage.heap_size(),
+            Hll::Sparse(sparse_registers) => sparse_registers.heap_size(),
+            Hll::Dense(dense_registers) => dense_registers.heap_size(),
+        }
+    }
+
+    /// is_empty returns true if no values have been added.  It inspects the storage directly
+    /// rather than estimating the cardinality.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Hll::Empty(_) => true,
+            Hll::Explicit(explicit_storage) => explicit_storage.is_empty(),
+            Hll::Sparse(sparse_registers) => sparse_registers.is_empty(),
+            Hll::Dense(dense_registers) => dense_registers.is_empty(),
+        }
+    }
+
+    fn estimate_cardinality(&self) -> f64 {
+        let (sum, num_of_zeros) = match self {
+            Hll::Empty(_) => return 0.0,
+            Hll::Explicit(explicit_storage) => return explicit_storage.len() as f64,
+            Hll::Sparse(sparse_registers) => sparse_registers.indicator(),
+            Hll::Dense(dense_registers) => dense_registers.indicator(),
+        };
+
+        Self::estimate_from_indicator(self.settings(), sum, num_of_zeros)
+    }
+
+    fn estimate_from_indicator(settings: &Settings, sum: f64, num_of_zeros: u32) -> f64 {
         // apply the estimate and correction to the indicator function
         let estimator = settings.alpha_msquared / sum;
 
+        if settings.bias_correction
+            && let Some(estimate) = bias::estimate(settings, estimator, num_of_zeros)
+        {
+            return estimate;
+        }
+
         if (num_of_zeros != 0) && (estimator < settings.small_estimator_cutoff) {
             // following documentation courtesy of the java implementation:
             // The "small range correction" formula from the HyperLogLog
             // algorithm. Only appropriate if both the estimator is smaller than
             // (5/2) * m and there are still registers that have the zero value.
             let num_of_zeros = num_of_zeros as f64;
-            let m: f64 = (1 << settings.log_2m).into();
-            let small_estimator = m * (m / num_of_zeros).ln();
-            return small_estimator.ceil() as u64;
+            let m = settings.registers() as f64;
+            let small_estimator = m * math::ln(m / num_of_zeros);
+            return small_estimator;
         }
 
+        // with no zero registers left the small range correction is undefined (ln(m / 0)), so an
+        // estimator under the small cutoff falls through to the raw estimator.  This matches the
+        // java and postgres implementations.
         if estimator <= settings.large_estimator_cutoff {
-            return estimator.ceil() as u64;
+            return estimator;
         }
 
         // following documentation courtesy of the java implementation:
         // The "large range correction" formula from the HyperLogLog algorithm,
         // adapted for 64 bit hashes. Only appropriate for estimators whose
         // value exceeds the calculated cutoff.
-        let large_estimator =
-            -1.0 * settings.two_to_l * (1.0 - (estimator / settings.two_to_l)).ln();
-        large_estimator.ceil() as u64
+        //
+        // the correction diverges as the estimator reaches 2^L, which small register widths can
+        // do.  Saturate just below it so the result stays finite and never drops as registers
+        // rise, rather than taking the log of a negative number.
+        let ratio = (estimator / settings.two_to_l).min(1.0 - f64::EPSILON);
+        -settings.two_to_l * math::ln(1.0 - ratio)
+    }
+
+    /// will_transition_soon returns true if adding `within` more distinct values could move the Hll
+    /// to a larger storage representation.  Dense storage never transitions.
+    pub fn will_transition_soon(&self, within: usize) -> bool {
+        match self {
+            Hll::Empty(settings) => {
+                within > 0
+                    && settings.explicit_threshold() == 0
+                    && settings.sparse_threshold.is_none()
+            }
+            Hll::Explicit(explicit_storage) => {
+                (explicit_storage.len() as usize).saturating_add(within)
+                    > explicit_storage.settings.explicit_threshold() as usize
+            }
+            Hll::Sparse(sparse_registers) => match sparse_registers.settings.sparse_threshold {
+                Some(threshold) => {
+                    sparse_registers.len().saturating_add(within) > threshold as usize
+                }
+                None => true,
+            },
+            Hll::Dense(_) => false,
+        }
     }
 
     fn is_full(&self) -> bool {
@@ -277,6 +1029,28 @@ impl Hll {
         }
     }
 
+    /// precision returns log_2m, the log base 2 of the number of registers.  Hlls can only be unioned
+    /// if their precision and register width match.
+    pub fn precision(&self) -> u32 {
+        self.settings().log_2m
+    }
+
+    /// register_width returns the number of bits in each register.
+    pub fn register_width(&self) -> u32 {
+        self.settings().reg_width
+    }
+
+    /// explicit_threshold returns the number of values stored explicitly before converting to
+    /// registers, with the automatic threshold resolved.  0 means explicit storage is disabled.
+    pub fn explicit_threshold(&self) -> u32 {
+        self.settings().explicit_threshold()
+    }
+
+    /// sparse_enabled returns true if the Hll uses sparse storage before dense storage.
+    pub fn sparse_enabled(&self) -> bool {
+        self.settings().sparse_threshold.is_some()
+    }
+
     pub fn clone_with_settings(&self, settings: &Settings) -> Self {
         match self {
             Hll::Empty(_) => Hll::Empty(*settings),
@@ -302,73 +1076,348 @@ impl Hll {
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
-        let (settings, type_id, size) = match self {
-            Hll::Empty(settings) => (settings, 1, 0),
-            Hll::Explicit(explicit_storage) => {
-                (&explicit_storage.settings, 2, explicit_storage.bytes_size())
-            }
-            Hll::Sparse(sparse_registers) => {
-                (&sparse_registers.settings, 3, sparse_registers.bytes_size())
-            }
-            Hll::Dense(dense_registers) => {
-                (&dense_registers.settings, 4, dense_registers.bytes_size())
+        let mut res: Vec<u8> = vec![0; self.serialized_len()];
+        self.write_header(&mut res);
+        self.write_storage(&mut res[HEADER_LEN..]);
+
+        res
+    }
+
+    /// to_bytes_with_checksum is `to_bytes` followed by a big endian CRC32 of the serialized bytes,
+    /// which `from_bytes` verifies.  The checksum is flagged in the top bit of the cutoff byte,
+    /// which the storage spec leaves unused, so other implementations may not accept the result.
+    pub fn to_bytes_with_checksum(&self) -> Vec<u8> {
+        let mut res = self.to_bytes();
+        res[2] |= CHECKSUM_FLAG;
+        let checksum = crc32(&res);
+        res.extend_from_slice(&checksum.to_be_bytes());
+
+        res
+    }
+
+    /// to_bytes_ordered is `to_bytes` with Explicit values written in the given order.  Other storage
+    /// types don't depend on the order.  `from_bytes` accepts explicit values in any order, but
+    /// only `ExplicitOrder::Signed` output is byte for byte identical to the PostgreSQL and Java
+    /// implementations.  Values are deduplicated in sorted order, so insertion order isn't known.
+    pub fn to_bytes_ordered(&self, order: ExplicitOrder) -> Vec<u8> {
+        let Hll::Explicit(explicit_storage) = self else {
+            return self.to_bytes();
+        };
+
+        let mut res: Vec<u8> = vec![0; self.serialized_len()];
+        self.write_header(&mut res);
+        explicit_storage.to_bytes_ordered(order, &mut res[HEADER_LEN..]);
+
+        res
+    }
+
+    /// serialized_len returns the number of bytes written by `to_bytes` and `write_to`.
+    pub fn serialized_len(&self) -> usize {
+        HEADER_LEN + self.storage_bytes_size()
+    }
+
+    /// write_to serializes the Hll into the start of buf like `to_bytes` and returns the number of
+    /// bytes written.  It fails with `HllError::Truncated` if buf is shorter than `serialized_len`.
+    pub fn write_to(&self, buf: &mut [u8]) -> Result<usize, HllError> {
+        let len = self.serialized_len();
+        check_len(buf, len)?;
+
+        let buf = &mut buf[..len];
+        // sparse registers are or'ed into place so the storage has to start out zeroed
+        buf.fill(0);
+        self.write_header(buf);
+        self.write_storage(&mut buf[HEADER_LEN..]);
+
+        Ok(len)
+    }
+
+    fn write_header(&self, buf: &mut [u8]) {
+        buf[..HEADER_LEN].copy_from_slice(&self.settings().serialized_header_bytes(self.type_id()));
+    }
+
+    /// peek_header parses only the header of a serialized Hll and returns its storage type, as
+    /// returned by `type_id`, and settings.  The storage isn't read, so this is cheap even for large
+    /// dense Hlls and does not validate the storage or checksum.
+    pub fn peek_header(buf: &[u8]) -> Result<(u8, Settings), HllError> {
+        check_len(buf, HEADER_LEN)?;
+        let type_id = Self::read_version_type(buf[0])?;
+        if !(1..=4).contains(&type_id) {
+            return Err(HllError::InvalidTypeId(type_id));
+        }
+
+        Ok((type_id, Settings::from_bytes(&buf[1..3])?))
+    }
+
+    /// from_bytes parses an Hll written by `to_bytes` or `to_bytes_with_checksum`.  The checksum is
+    /// verified if the header says there is one.
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, HllError> {
+        check_len(buf, HEADER_LEN)?;
+        let buf = strip_checksum(buf)?;
+        let settings = Settings::from_bytes(&buf[1..3])?;
+
+        Self::read_storage(buf[0], settings, &buf[HEADER_LEN..])
+    }
+
+    /// read_one parses the Hll at the start of buf and returns it with the number of bytes it took
+    /// up, so Hlls written back to back can be read one at a time.  The storage spec doesn't record
+    /// the length of the storage, it's only implied for Empty and Dense storage.  Explicit and
+    /// Sparse storage fail with `HllError::UnknownLength` and must be converted with `into_dense`
+    /// before they're written to a stream.
+    pub fn read_one(buf: &[u8]) -> Result<(Self, usize), HllError> {
+        let (type_id, settings) = Self::peek_header(buf)?;
+        let mut len = HEADER_LEN
+            + match type_id {
+                1 => 0,
+                4 => settings.dense_bytes_size(),
+                _ => return Err(HllError::UnknownLength(type_id)),
+            };
+        if buf[2] & CHECKSUM_FLAG != 0 {
+            len += CHECKSUM_LEN;
+        }
+
+        check_len(buf, len)?;
+        Ok((Self::from_bytes(&buf[..len])?, len))
+    }
+
+    /// cardinality_from_bytes returns the cardinality of a serialized Hll.  Dense registers, the only
+    /// storage whose size is significant, are scanned in place rather than copied into an Hll.
+    pub fn cardinality_from_bytes(buf: &[u8]) -> Result<u64, HllError> {
+        check_len(buf, HEADER_LEN)?;
+        let buf = strip_checksum(buf)?;
+        let settings = Settings::from_bytes(&buf[1..3])?;
+        let storage = &buf[HEADER_LEN..];
+
+        let (sum, num_of_zeros) = match buf[0] {
+            0x14 => DenseRegistersRef::new(&settings, storage)?.indicator(),
+            version_type => {
+                return Ok(Self::read_storage(version_type, settings, storage)?.cardinality());
             }
         };
-        let mut res: Vec<u8> = vec![0; 3 + size];
 
-        res[0] = (1 << 4) | type_id;
-        res[1] = (((settings.reg_width - 1) << 5) | settings.log_2m) as u8;
-        res[2] = settings.pack_cutoff_byte();
+        Ok(math::ceil(Self::estimate_from_indicator(&settings, sum, num_of_zeros)) as u64)
+    }
+
+    /// to_bytes_minimal serializes the Hll without the settings bytes of the header, leaving only
+    /// the version/type byte and the storage.  This is meant for closed systems where every Hll
+    /// shares the same settings, which then have to be supplied to `from_bytes_minimal`.
+    pub fn to_bytes_minimal(&self) -> Vec<u8> {
+        let mut res: Vec<u8> = vec![0; 1 + self.storage_bytes_size()];
+
+        res[0] = self.version_type_byte();
+        self.write_storage(&mut res[1..]);
+
+        res
+    }
+
+    /// from_bytes_minimal parses an Hll written by `to_bytes_minimal` using the provided settings.
+    pub fn from_bytes_minimal(settings: Settings, buf: &[u8]) -> Result<Self, HllError> {
+        check_len(buf, 1)?;
+        Self::read_storage(buf[0], settings, &buf[1..])
+    }
+
+    fn version_type_byte(&self) -> u8 {
+        (SERIALIZATION_VERSION << 4) | self.type_id()
+    }
+
+    /// read_version_type checks the version of the version/type byte and returns the type.
+    fn read_version_type(version_type: u8) -> Result<u8, HllError> {
+        let version = version_type >> 4;
+        if version != SERIALIZATION_VERSION {
+            return Err(HllError::Version(version));
+        }
+
+        Ok(version_type & 0x0F)
+    }
 
+    fn storage_bytes_size(&self) -> usize {
+        match self {
+            Hll::Empty(_) => 0,
+            Hll::Explicit(explicit_storage) => explicit_storage.bytes_size(),
+            Hll::Sparse(sparse_registers) => sparse_registers.bytes_size(),
+            Hll::Dense(dense_registers) => dense_registers.bytes_size(),
+        }
+    }
+
+    fn write_storage(&self, buf: &mut [u8]) {
         match self {
             Hll::Empty(_settings) => {}
             Hll::Explicit(explicit_storage) => {
-                explicit_storage.to_bytes(&mut res[3..]);
+                explicit_storage.to_bytes(buf);
             }
             Hll::Sparse(sparse_registers) => {
-                sparse_registers.to_bytes(&mut res[3..]);
+                sparse_registers.to_bytes(buf);
             }
             Hll::Dense(dense_registers) => {
-                dense_registers.to_bytes(&mut res[3..]);
+                dense_registers.to_bytes(buf);
             }
         }
+    }
 
-        res
+    fn read_storage(version_type: u8, settings: Settings, buf: &[u8]) -> Result<Self, HllError> {
+        let type_id = Self::read_version_type(version_type)?;
+        let storage = match type_id {
+            1 if buf.is_empty() => Self::Empty(settings),
+            1 => {
+                return Err(HllError::StorageLength {
+                    type_id,
+                    len: buf.len(),
+                });
+            }
+            2 => Self::Explicit(ExplicitStorage::from_bytes(&settings, buf)?),
+            3 => Self::Sparse(SparseRegisters::from_bytes(&settings, buf)?),
+            4 => Self::Dense(DenseRegisters::from_bytes(&settings, buf)?),
+            _ => {
+                return Err(HllError::InvalidTypeId(type_id));
+            }
+        };
+
+        Ok(storage)
     }
 
-    pub fn from_bytes(buf: &[u8]) -> Result<Self, HllError> {
-        let version = buf[0] >> 4;
-        let type_id = buf[0] & 0x0F;
+    /// register_touch_counts returns how many times each register was the target of an insert. Skewed
+    /// counts indicate a poorly distributed hash. This is diagnostic only and not part of the sketch,
+    /// so it is only available for dense storage built with the `touch-counts` feature.  Inserts made
+    /// while the Hll was still Sparse are included.
+    pub fn register_touch_counts(&self) -> Option<Vec<u32>> {
+        match self {
+            #[cfg(feature = "touch-counts")]
+            Hll::Dense(dense_registers) => Some(dense_registers.touch_counts().to_vec()),
+            _ => None,
+        }
+    }
 
-        if version != 1 {
-            return Err(HllError::Version(version));
+    /// logically_eq compares the register values of two Hlls regardless of how they are stored, so
+    /// a Sparse Hll equals its Dense conversion.  Explicit storage is compared by the registers its
+    /// values would set.  Hlls with different log_2m or reg_width are never equal.
+    pub fn logically_eq(&self, other: &Self) -> bool {
+        if self.settings_check(other).is_err() {
+            return false;
         }
 
-        let reg_width = (buf[1] >> 5) + 1;
-        let log_2m = buf[1] & 0x1F;
-        let (sparse_enabled, explicit_threshold) = Settings::unpack_cutoff_byte(buf[2]);
+        self.nonzero_registers().eq(other.nonzero_registers())
+    }
 
-        let settings = Settings::new(
-            log_2m as u32,
-            reg_width as u32,
-            explicit_threshold,
-            sparse_enabled,
-        )?;
+    /// is_subset_of returns true if no register of self is greater than the same register of other,
+    /// as is the case when self has been unioned into other.  This is an exact comparison of the
+    /// registers regardless of how they are stored, not an estimate of whether the set of values
+    /// added to self is contained in other's.  The Hlls must have the same log_2m and reg_width.
+    pub fn is_subset_of(&self, other: &Self) -> Result<bool, HllError> {
+        self.settings_check(other)?;
 
-        let storage = match type_id {
-            1 => Self::Empty(settings),
-            2 => Self::Explicit(ExplicitStorage::from_bytes(&settings, &buf[3..])),
-            3 => Self::Sparse(SparseRegisters::from_bytes(&settings, &buf[3..])),
-            4 => Self::Dense(DenseRegisters::from_bytes(&settings, &buf[3..])),
-            _ => {
-                return Err(HllError::Version(type_id));
+        // merge join of the two sorted register lists, registers missing from other are 0
+        let mut other_registers = other.nonzero_registers().peekable();
+        for (reg_num, value) in self.nonzero_registers() {
+            let other_value = loop {
+                match other_registers.peek() {
+                    Some((other_reg_num, _)) if *other_reg_num < reg_num => {
+                        other_registers.next();
+                    }
+                    Some((other_reg_num, other_value)) if *other_reg_num == reg_num => {
+                        break *other_value;
+                    }
+                    _ => break 0,
+                }
+            };
+
+            if value > other_value {
+                return Ok(false);
             }
+        }
+
+        Ok(true)
+    }
+
+    /// registers yields (reg_num, value) pairs in register order.  Dense storage yields every
+    /// register while Sparse storage only yields the registers that are set, the rest are 0.
+    /// Explicit storage is materialized into the registers its values would set and Empty storage
+    /// yields nothing.
+    pub fn registers(&self) -> impl Iterator<Item = (u32, u8)> + '_ {
+        let registers: Box<dyn Iterator<Item = (u32, u8)> + '_> = match self {
+            Hll::Empty(_) => Box::new(core::iter::empty()),
+            Hll::Explicit(explicit_storage) => {
+                let registers: Vec<(u32, u8)> =
+                    explicit_storage.as_registers().registers().collect();
+                Box::new(registers.into_iter())
+            }
+            Hll::Sparse(sparse_registers) => Box::new(sparse_registers.iter().copied()),
+            Hll::Dense(dense_registers) => Box::new(dense_registers.iter()),
         };
 
-        Ok(storage)
+        registers
+    }
+
+    /// nonzero_registers yields (reg_num, value) for each register that is set, in register order.
+    fn nonzero_registers(&self) -> impl Iterator<Item = (u32, u8)> + '_ {
+        self.registers().filter(|(_, value)| *value != 0)
+    }
+
+    /// compact converts Dense storage back to Sparse when few enough registers are set to fit under
+    /// the sparse threshold, as can happen after a `clear` or a union of mostly empty Hlls.  Other
+    /// storage, or settings without sparse storage, are left alone.
+    pub fn compact(&mut self) {
+        if let Hll::Dense(dense_registers) = self
+            && let Some(sparse_registers) = dense_registers.to_sparse()
+        {
+            *self = Hll::Sparse(sparse_registers);
+        }
+    }
+
+    /// into_dense converts the Hll to Dense storage, whatever it currently uses.
+    pub fn into_dense(self) -> Self {
+        match self {
+            Hll::Empty(settings) => Hll::Dense(DenseRegisters::with_settings(&settings)),
+            Hll::Explicit(explicit_storage) => {
+                let mut dense_registers = DenseRegisters::with_settings(&explicit_storage.settings);
+                dense_registers.union_explicit(&explicit_storage);
+                Hll::Dense(dense_registers)
+            }
+            Hll::Sparse(sparse_registers) => Hll::Dense(sparse_registers.to_dense(None)),
+            Hll::Dense(_) => self,
+        }
+    }
+
+    /// into_sparse converts the Hll to Sparse storage, whatever it currently uses.  It fails if the
+    /// settings disable sparse storage or if more registers are set than the sparse threshold.
+    pub fn into_sparse(self) -> Result<Self, HllError> {
+        let settings = *self.settings();
+        let Some(threshold) = settings.sparse_threshold else {
+            return Err(HllError::SparseDisabled);
+        };
+
+        let sparse_registers = match self {
+            Hll::Empty(settings) => SparseRegisters::with_settings(&settings),
+            Hll::Explicit(explicit_storage) => {
+                let mut sparse_registers = SparseRegisters::with_settings(&settings);
+                sparse_registers.union_explicit(&explicit_storage);
+                sparse_registers
+            }
+            Hll::Sparse(sparse_registers) => sparse_registers,
+            Hll::Dense(dense_registers) => match dense_registers.to_sparse() {
+                Some(sparse_registers) => sparse_registers,
+                None => return Err(HllError::SparseThreshold(threshold)),
+            },
+        };
+
+        if sparse_registers.is_full() {
+            return Err(HllError::SparseThreshold(threshold));
+        }
+
+        Ok(Hll::Sparse(sparse_registers))
     }
 
+    /// clear resets the Hll to Empty, freeing its storage.
     pub fn clear(&mut self) {
+        *self = self.clone_empty();
+    }
+
+    /// clone_empty returns an Empty Hll with the same settings, e.g. to start a new window.
+    pub fn clone_empty(&self) -> Hll {
+        Hll::Empty(*self.settings())
+    }
+
+    /// clear_in_place zeroes the current storage without changing its type, keeping the allocation
+    /// for reuse.
+    pub fn clear_in_place(&mut self) {
         match self {
             Hll::Empty(_) => {}
             Hll::Explicit(explicit_storage) => explicit_storage.clear(),
@@ -376,6 +1425,133 @@ impl Hll {
             Hll::Dense(dense_registers) => dense_registers.clear(),
         }
     }
+
+    /// shrink_to_fit releases capacity the Explicit and Sparse storage no longer uses.  Neither
+    /// shrinks on its own, so after `clear_in_place`, or after most values are dropped by other
+    /// operations, they keep the allocation of their largest size.  Explicit storage rebuilds its
+    /// hash table with the fewest slots that hold its values and Sparse storage trims its register
+    /// list.  Dense storage is always exactly sized.
+    pub fn shrink_to_fit(&mut self) {
+        match self {
+            Hll::Empty(_) | Hll::Dense(_) => {}
+            Hll::Explicit(explicit_storage) => explicit_storage.shrink_to_fit(),
+            Hll::Sparse(sparse_registers) => sparse_registers.shrink_to_fit(),
+        }
+    }
+}
+
+/// Collects already hashed values into an Hll with the `SettingsBuilder` defaults: log_2m 11,
+/// reg_width 5, an automatic explicit threshold and sparse enabled.  Use
+/// `Hll::from_iter_with_settings` for other settings.
+impl FromIterator<u64> for Hll {
+    fn from_iter<I: IntoIterator<Item = u64>>(values: I) -> Self {
+        let settings = SettingsBuilder::default()
+            .build()
+            .expect("default settings are valid");
+
+        Self::from_iter_with_settings(settings, values)
+    }
+}
+
+/// Adds already hashed values with `add_raw`.
+impl Extend<u64> for Hll {
+    fn extend<I: IntoIterator<Item = u64>>(&mut self, values: I) {
+        for value in values {
+            self.add_raw(value);
+        }
+    }
+}
+
+impl From<ExplicitStorage> for Hll {
+    fn from(explicit_storage: ExplicitStorage) -> Self {
+        Hll::Explicit(explicit_storage)
+    }
+}
+
+impl From<SparseRegisters> for Hll {
+    fn from(sparse_registers: SparseRegisters) -> Self {
+        Hll::Sparse(sparse_registers)
+    }
+}
+
+impl From<DenseRegisters> for Hll {
+    fn from(dense_registers: DenseRegisters) -> Self {
+        Hll::Dense(dense_registers)
+    }
+}
+
+/// Deserializes storage spec bytes with `Hll::from_bytes`.
+impl TryFrom<&[u8]> for Hll {
+    type Error = HllError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Hll::from_bytes(bytes)
+    }
+}
+
+/// Serializes to storage spec bytes with `Hll::to_bytes`.
+impl From<&Hll> for Vec<u8> {
+    fn from(hll: &Hll) -> Self {
+        hll.to_bytes()
+    }
+}
+
+/// Serializes to storage spec bytes with `Hll::to_bytes`.
+impl From<Hll> for Vec<u8> {
+    fn from(hll: Hll) -> Self {
+        hll.to_bytes()
+    }
+}
+
+/// `&a | &b` returns the union of two Hlls.
+///
+/// # Panics
+///
+/// Panics if the settings of the operands don't match. Use `union` to handle the error instead.
+impl BitOr<&Hll> for &Hll {
+    type Output = Hll;
+
+    fn bitor(self, rhs: &Hll) -> Self::Output {
+        let mut res = self.clone();
+        res |= rhs;
+        res
+    }
+}
+
+/// `a |= &b` unions b into a.
+///
+/// # Panics
+///
+/// Panics if the settings of the operands don't match. Use `union` to handle the error instead.
+impl BitOrAssign<&Hll> for Hll {
+    fn bitor_assign(&mut self, rhs: &Hll) {
+        if let Err(err) = self.union(true, rhs) {
+            panic!("unable to union hll: {err}");
+        }
+    }
+}
+
+/// Summarizes the storage type, settings, estimated cardinality and heap size, e.g.
+/// `HLL(dense, log_2m=14, reg_width=6, cardinality≈1234567, heap=12288B)`.
+impl fmt::Display for Hll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let storage = match self {
+            Hll::Empty(_) => "empty",
+            Hll::Explicit(_) => "explicit",
+            Hll::Sparse(_) => "sparse",
+            Hll::Dense(_) => "dense",
+        };
+        let settings = self.settings();
+
+        write!(
+            f,
+            "HLL({storage}, log_2m={}, reg_width={}, cardinality≈{}, heap={}B)",
+            settings.log_2m,
+            settings.reg_width,
+            self.cardinality(),
+            self.heap_size()
+        )
+    }
 }
 
 #[cfg(test)]
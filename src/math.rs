@@ -0,0 +1,62 @@
+//! The float functions used by the estimators.  They are provided by `std`, and by `libm` in
+//! `no_std` builds.
+
+macro_rules! math_fn {
+    ($name:ident, $libm:ident) => {
+        #[cfg(feature = "std")]
+        #[inline(always)]
+        pub(crate) fn $name(x: f64) -> f64 {
+            x.$name()
+        }
+
+        #[cfg(not(feature = "std"))]
+        #[inline(always)]
+        pub(crate) fn $name(x: f64) -> f64 {
+            libm::$libm(x)
+        }
+    };
+}
+
+math_fn!(ln, log);
+math_fn!(log2, log2);
+math_fn!(exp, exp);
+math_fn!(ceil, ceil);
+math_fn!(round, round);
+math_fn!(sqrt, sqrt);
+
+#[cfg(feature = "std")]
+#[inline(always)]
+pub(crate) fn powf(x: f64, y: f64) -> f64 {
+    x.powf(y)
+}
+
+#[cfg(not(feature = "std"))]
+#[inline(always)]
+pub(crate) fn powf(x: f64, y: f64) -> f64 {
+    libm::pow(x, y)
+}
+
+#[cfg(test)]
+mod test {
+    use std::process::Command;
+
+    #[test]
+    fn no_std_build() {
+        // a separate target dir keeps this from waiting on the build lock held by `cargo test`
+        let status = Command::new(env!("CARGO"))
+            .args([
+                "check",
+                "--lib",
+                "--no-default-features",
+                "--features",
+                "libm",
+            ])
+            .arg("--target-dir")
+            .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/target/no_std"))
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .status()
+            .unwrap();
+
+        assert!(status.success());
+    }
+}
@@ -0,0 +1,83 @@
+use rayon::prelude::*;
+
+use crate::{Hll, HllError, Settings};
+
+// inputs up to this size are merged on the calling thread, and parallel jobs merge at least this
+// many Hlls each, since a single union is too cheap to be worth scheduling
+const PAR_UNION_MIN_LEN: usize = 16;
+
+impl Hll {
+    /// par_union merges all the Hlls into a new Hll with the given settings.  The Hlls are merged
+    /// in parallel batches whose results are then merged pairwise, small inputs are merged
+    /// sequentially.  strict has the same meaning as for `union` and applies to every merge.
+    pub fn par_union<I>(settings: Settings, strict: bool, iter: I) -> Result<Hll, HllError>
+    where
+        I: IntoParallelIterator<Item = Hll>,
+    {
+        let merge = |mut acc: Hll, other: Hll| -> Result<Hll, HllError> {
+            acc.union(strict, &other)?;
+            Ok(acc)
+        };
+
+        let hlls: Vec<Hll> = iter.into_par_iter().collect();
+        if hlls.len() <= PAR_UNION_MIN_LEN {
+            return hlls.into_iter().try_fold(Hll::new(settings), merge);
+        }
+
+        hlls.into_par_iter()
+            .with_min_len(PAR_UNION_MIN_LEN)
+            .try_fold(|| Hll::new(settings), merge)
+            .try_reduce(|| Hll::new(settings), merge)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Hll, HllError, Settings};
+
+    #[test]
+    fn par_union() {
+        let settings = Settings::new(11, 5, 0, true).unwrap();
+        for count in [0_u64, 8, 64, 10_000] {
+            let hlls: Vec<Hll> = (0..count)
+                .map(|i| {
+                    let mut hll = Hll::new(settings);
+                    hll.add(&i);
+                    hll
+                })
+                .collect();
+
+            let mut expected = Hll::new(settings);
+            hlls.iter()
+                .for_each(|hll| expected.union(true, hll).unwrap());
+
+            let union = Hll::par_union(settings, true, hlls).unwrap();
+            assert_eq!(expected, union);
+            if count <= 64 {
+                assert!(union.cardinality().abs_diff(count) <= 1, "{}", count);
+            }
+        }
+    }
+
+    #[test]
+    fn par_union_strict() {
+        let settings = Settings::new(11, 5, 0, true).unwrap();
+        let narrow = Settings::new(11, 4, 0, true).unwrap();
+        let hlls: Vec<Hll> = (0..64_u64)
+            .map(|i| {
+                let mut hll = Hll::new(if i == 40 { narrow } else { settings });
+                hll.add(&i);
+                hll
+            })
+            .collect();
+
+        assert!(matches!(
+            Hll::par_union(settings, true, hlls.clone()),
+            Err(HllError::Settings(_))
+        ));
+        assert_eq!(
+            64,
+            Hll::par_union(settings, false, hlls).unwrap().cardinality()
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use alloc::vec::Vec;
+use thiserror::Error;
+
+use crate::{Hll, HllError};
+
+/// PgHllError describes why a PostgreSQL hex encoded Hll (as output by `hll_out`, e.g. `\x128b7f`)
+/// could not be imported.
+#[derive(Clone, Debug, Error)]
+pub enum PgHllError {
+    #[error("missing \\x prefix")]
+    MissingPrefix,
+    #[error("hex string must have an even number of digits, found {0}")]
+    OddLength(usize),
+    /// position is the byte offset of the character in the input, including the `\x` prefix.
+    #[error("invalid hex character {character:?} at byte {position}")]
+    InvalidHex { position: usize, character: char },
+    #[error("invalid hll: {0}")]
+    Hll(#[from] HllError),
+}
+
+impl Hll {
+    /// try_from_pg_hex parses the hex text representation used by the PostgreSQL hll extension.
+    pub fn try_from_pg_hex(s: &str) -> Result<Self, PgHllError> {
+        let digits = s.strip_prefix("\\x").ok_or(PgHllError::MissingPrefix)?;
+        let bytes = decode_hex(digits)?;
+
+        Ok(Self::from_bytes(&bytes)?)
+    }
+}
+
+fn decode_hex(digits: &str) -> Result<Vec<u8>, PgHllError> {
+    // positions are reported as byte offsets into the full input, including the prefix
+    let invalid = |position: usize, character: char| PgHllError::InvalidHex {
+        position: position + 2,
+        character,
+    };
+
+    // hex digits are ASCII, so once everything else is rejected bytes and characters line up
+    if let Some((i, character)) = digits.char_indices().find(|(_, c)| !c.is_ascii()) {
+        return Err(invalid(i, character));
+    }
+
+    let digits = digits.as_bytes();
+    if !digits.len().is_multiple_of(2) {
+        return Err(PgHllError::OddLength(digits.len()));
+    }
+
+    let nibble = |position: usize| {
+        let character = digits[position] as char;
+        character
+            .to_digit(16)
+            .map(|d| d as u8)
+            .ok_or_else(|| invalid(position, character))
+    };
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| Ok((nibble(i)? << 4) | nibble(i + 1)?))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::PgHllError;
+    use crate::{Hll, HllError};
+
+    #[test]
+    fn try_from_pg_hex() {
+        let hll = Hll::try_from_pg_hex("\\x118b7f").unwrap();
+        assert!(matches!(hll, Hll::Empty(_)));
+
+        let hll = Hll::try_from_pg_hex("\\x128b7f0000000000000001").unwrap();
+        assert_eq!(1, hll.cardinality());
+    }
+
+    #[test]
+    fn try_from_pg_hex_errors() {
+        assert!(matches!(
+            Hll::try_from_pg_hex("118b7f"),
+            Err(PgHllError::MissingPrefix)
+        ));
+
+        assert!(matches!(
+            Hll::try_from_pg_hex("\\x118b7"),
+            Err(PgHllError::OddLength(5))
+        ));
+
+        assert!(matches!(
+            Hll::try_from_pg_hex("\\x118z7f"),
+            Err(PgHllError::InvalidHex {
+                position: 5,
+                character: 'z'
+            })
+        ));
+
+        // a multibyte character can't hide in an otherwise even number of bytes
+        for (hex, position) in [("\\x118b7f\u{e9}", 8), ("\\x11\u{e9}8b7", 4)] {
+            assert!(
+                matches!(
+                    Hll::try_from_pg_hex(hex),
+                    Err(PgHllError::InvalidHex {
+                        position: p,
+                        character: '\u{e9}'
+                    }) if p == position
+                ),
+                "{}",
+                hex
+            );
+        }
+
+        assert!(matches!(
+            Hll::try_from_pg_hex("\\x218b7f"),
+            Err(PgHllError::Hll(HllError::Version(2)))
+        ));
+    }
+}
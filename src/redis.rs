@@ -0,0 +1,235 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use thiserror::Error;
+
+use crate::{Hll, HllError, Settings, SettingsError};
+
+// redis uses 2^14 registers of 6 bits
+const REDIS_LOG_2M: u32 = 14;
+const REDIS_REG_WIDTH: u32 = 6;
+const REDIS_REGISTERS: u32 = 1 << REDIS_LOG_2M;
+
+// the header is the magic, the encoding, 3 unused bytes and the cached cardinality as a little
+// endian u64 whose most significant bit marks the cache as invalid
+const REDIS_MAGIC: &[u8; 4] = b"HYLL";
+const REDIS_HEADER_LEN: usize = 16;
+const REDIS_DENSE: u8 = 0;
+const REDIS_SPARSE: u8 = 1;
+const REDIS_DENSE_LEN: usize = REDIS_HEADER_LEN + (REDIS_REGISTERS * REDIS_REG_WIDTH) as usize / 8;
+
+/// RedisHllError describes why a Redis HyperLogLog (as returned by `GET` on a `PFADD` key) could
+/// not be imported.
+#[derive(Clone, Debug, Error)]
+pub enum RedisHllError {
+    #[error("missing HYLL magic")]
+    Magic,
+    #[error("unsupported encoding {0}")]
+    Encoding(u8),
+    #[error("dense encoding requires {REDIS_DENSE_LEN} bytes, found {0}")]
+    DenseLength(usize),
+    #[error("sparse encoding describes {0} registers instead of {REDIS_REGISTERS}")]
+    SparseRegisterCount(u32),
+}
+
+impl Hll {
+    /// from_redis_bytes imports a Redis HyperLogLog in either its dense or sparse encoding.  Redis
+    /// uses 2^14 registers of 6 bits so the result has log_2m 14 and reg_width 6, with explicit
+    /// storage disabled since Redis only keeps registers.
+    ///
+    /// Only the registers are imported.  Redis hashes values with MurmurHash64A, so values added
+    /// after the import need to be hashed the same way and added with `add_raw` to be consistent.
+    /// Redis can set a register to 51, one more than the 50 hash bits above the register index
+    /// allow here, which fails with `HllError::RegisterValue` like any other out of range value.
+    pub fn from_redis_bytes(buf: &[u8]) -> Result<Self, HllError> {
+        if buf.len() < REDIS_HEADER_LEN || &buf[..REDIS_MAGIC.len()] != REDIS_MAGIC {
+            return Err(RedisHllError::Magic.into());
+        }
+
+        let registers = match buf[REDIS_MAGIC.len()] {
+            REDIS_DENSE => read_dense(buf)?,
+            REDIS_SPARSE => read_sparse(&buf[REDIS_HEADER_LEN..])?,
+            encoding => return Err(RedisHllError::Encoding(encoding).into()),
+        };
+
+        let settings = Settings::new(REDIS_LOG_2M, REDIS_REG_WIDTH, 0, true)?;
+        Self::from_register_map(settings, &registers)
+    }
+
+    /// to_redis_dense_bytes serializes the Hll in the dense encoding of Redis.  The Hll must have
+    /// log_2m 14 and reg_width 6.  The cached cardinality is marked invalid so Redis computes it
+    /// on the next `PFCOUNT`.
+    pub fn to_redis_dense_bytes(&self) -> Result<Vec<u8>, HllError> {
+        let settings = self.settings();
+        if settings.log_2m != REDIS_LOG_2M || settings.reg_width != REDIS_REG_WIDTH {
+            return Err(SettingsError::MisMatch.into());
+        }
+
+        let mut res = vec![0; REDIS_DENSE_LEN];
+        res[..REDIS_MAGIC.len()].copy_from_slice(REDIS_MAGIC);
+        res[REDIS_MAGIC.len()] = REDIS_DENSE;
+        res[REDIS_HEADER_LEN - 1] = 1 << 7;
+
+        let registers = &mut res[REDIS_HEADER_LEN..];
+        for (reg_num, value) in self.registers() {
+            // registers are packed starting from the least significant bit of each byte
+            let bit = (reg_num * REDIS_REG_WIDTH) as usize;
+            let word = (value as u16) << (bit % 8);
+            registers[bit / 8] |= word as u8;
+            if let Some(next) = registers.get_mut(bit / 8 + 1) {
+                *next |= (word >> 8) as u8;
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+fn read_dense(buf: &[u8]) -> Result<BTreeMap<u32, u8>, RedisHllError> {
+    if buf.len() != REDIS_DENSE_LEN {
+        return Err(RedisHllError::DenseLength(buf.len()));
+    }
+
+    let registers = &buf[REDIS_HEADER_LEN..];
+    let mask = (1 << REDIS_REG_WIDTH) - 1;
+
+    Ok((0..REDIS_REGISTERS)
+        .map(|reg_num| {
+            let bit = (reg_num * REDIS_REG_WIDTH) as usize;
+            let low = registers[bit / 8] as u16;
+            let high = registers.get(bit / 8 + 1).copied().unwrap_or(0) as u16;
+
+            (reg_num, (((low | (high << 8)) >> (bit % 8)) & mask) as u8)
+        })
+        .filter(|(_, value)| *value != 0)
+        .collect())
+}
+
+/// read_sparse decodes the run length opcodes of the sparse encoding:
+/// 00xxxxxx is a run of xxxxxx + 1 zero registers,
+/// 01xxxxxx yyyyyyyy is a run of xxxxxxyyyyyyyy + 1 zero registers and
+/// 1vvvvvxx is a run of xx + 1 registers holding vvvvv + 1.
+fn read_sparse(buf: &[u8]) -> Result<BTreeMap<u32, u8>, RedisHllError> {
+    let mut registers = BTreeMap::new();
+    let mut reg_num: u32 = 0;
+
+    let mut bytes = buf.iter();
+    while let Some(op) = bytes.next() {
+        match op >> 6 {
+            0b00 => reg_num += (op & 0x3f) as u32 + 1,
+            0b01 => {
+                let low = *bytes
+                    .next()
+                    .ok_or(RedisHllError::SparseRegisterCount(reg_num))?;
+                reg_num += ((((op & 0x3f) as u32) << 8) | low as u32) + 1;
+            }
+            _ => {
+                let value = ((op >> 2) & 0x1f) + 1;
+                for _ in 0..(op & 0x03) + 1 {
+                    registers.insert(reg_num, value);
+                    reg_num += 1;
+                }
+            }
+        }
+
+        if reg_num > REDIS_REGISTERS {
+            return Err(RedisHllError::SparseRegisterCount(reg_num));
+        }
+    }
+
+    if reg_num != REDIS_REGISTERS {
+        return Err(RedisHllError::SparseRegisterCount(reg_num));
+    }
+
+    Ok(registers)
+}
+
+#[cfg(test)]
+mod test {
+    use super::RedisHllError;
+    use crate::{Hll, HllError, Settings};
+
+    // `PFADD key` on a missing key followed by `GET key`
+    const EMPTY_SPARSE: &[u8] = b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x7f\xff";
+
+    #[test]
+    fn from_redis_sparse() {
+        let hll = Hll::from_redis_bytes(EMPTY_SPARSE).unwrap();
+        assert_eq!(0, hll.cardinality());
+        assert_eq!(14, hll.settings().log_2m);
+        assert_eq!(6, hll.settings().reg_width);
+
+        // 1000 zeros, register 1000 = 3, registers 1001-1002 = 32, 15381 zeros
+        let mut buf = EMPTY_SPARSE[..16].to_vec();
+        buf.extend_from_slice(&[0x43, 0xe7, 0x88, 0xfd, 0x7c, 0x14]);
+        let hll = Hll::from_redis_bytes(&buf).unwrap();
+        assert_eq!(
+            vec![(1000, 3), (1001, 32), (1002, 32)],
+            hll.registers().filter(|(_, v)| *v != 0).collect::<Vec<_>>()
+        );
+
+        buf.pop();
+        assert!(matches!(
+            Hll::from_redis_bytes(&buf),
+            Err(HllError::Redis(RedisHllError::SparseRegisterCount(_)))
+        ));
+    }
+
+    #[test]
+    fn redis_dense_round_trip() {
+        let settings = Settings::new(14, 6, 0, false).unwrap();
+        let mut hll = Hll::new(settings);
+        for i in 0..100_000 {
+            hll.add(&i);
+        }
+
+        let bytes = hll.to_redis_dense_bytes().unwrap();
+        assert_eq!(16 + 12288, bytes.len());
+        assert_eq!(b"HYLL\x00", &bytes[..5]);
+
+        let round_trip = Hll::from_redis_bytes(&bytes).unwrap();
+        assert!(hll.logically_eq(&round_trip));
+        assert_eq!(hll.cardinality(), round_trip.cardinality());
+
+        // register 1 occupies bits 6-11
+        let mut hll = Hll::new(settings);
+        hll.add_raw((0b10000 << 14) | 1);
+        let bytes = hll.to_redis_dense_bytes().unwrap();
+        assert_eq!([0x40, 0x01, 0x00], bytes[16..19]);
+    }
+
+    #[test]
+    fn redis_errors() {
+        assert!(matches!(
+            Hll::from_redis_bytes(b"HYLX\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00"),
+            Err(HllError::Redis(RedisHllError::Magic))
+        ));
+        assert!(matches!(
+            Hll::from_redis_bytes(&EMPTY_SPARSE[..16]),
+            Err(HllError::Redis(RedisHllError::SparseRegisterCount(0)))
+        ));
+        assert!(matches!(
+            Hll::from_redis_bytes(b"HYLL\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00"),
+            Err(HllError::Redis(RedisHllError::DenseLength(16)))
+        ));
+        assert!(matches!(
+            Hll::from_redis_bytes(b"HYLL\x02\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00"),
+            Err(HllError::Redis(RedisHllError::Encoding(2)))
+        ));
+
+        // only the dense encoding can hold values above 32
+        let mut buf = b"HYLL\x00".to_vec();
+        buf.resize(16 + 12288, 0);
+        buf[16] = 51;
+        assert!(matches!(
+            Hll::from_redis_bytes(&buf),
+            Err(HllError::RegisterValue(51))
+        ));
+
+        let hll = Hll::new(Settings::new(11, 5, -1, true).unwrap());
+        assert!(matches!(
+            hll.to_redis_dense_bytes(),
+            Err(HllError::Settings(_))
+        ));
+    }
+}
@@ -0,0 +1,86 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Hll;
+
+/// `Hll` serializes as the bytes of the storage spec, see `Hll::to_bytes`.
+impl Serialize for Hll {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+impl<'de> Deserialize<'de> for Hll {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_bytes(HllVisitor)
+    }
+}
+
+struct HllVisitor;
+
+impl<'de> Visitor<'de> for HllVisitor {
+    type Value = Hll;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("hll storage spec bytes")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Hll::from_bytes(v).map_err(E::custom)
+    }
+
+    // formats without a native bytes type, like json, represent them as a sequence
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut buf = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(b) = seq.next_element()? {
+            buf.push(b);
+        }
+
+        self.visit_bytes(&buf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Hll, Settings};
+
+    fn hlls() -> Vec<Hll> {
+        [(-1, true), (0, true), (0, false)]
+            .into_iter()
+            .map(|(explicit_threshold, sparse_enabled)| {
+                let mut hll =
+                    Hll::new(Settings::new(11, 5, explicit_threshold, sparse_enabled).unwrap());
+                for i in 0..100 {
+                    hll.add(&i);
+                }
+                hll
+            })
+            .chain([Hll::new(Settings::new(11, 5, -1, true).unwrap())])
+            .collect()
+    }
+
+    #[test]
+    fn json() {
+        for hll in hlls() {
+            let json = serde_json::to_string(&hll).unwrap();
+            assert_eq!(hll, serde_json::from_str::<Hll>(&json).unwrap());
+        }
+
+        assert!(serde_json::from_str::<Hll>("[20, 139]").is_err());
+        assert!(serde_json::from_str::<Hll>("[20, 139, 127, 0]").is_err());
+    }
+
+    #[test]
+    fn bincode() {
+        for hll in hlls() {
+            let bytes = bincode::serialize(&hll).unwrap();
+            assert_eq!(hll, bincode::deserialize::<Hll>(&bytes).unwrap());
+        }
+
+        let bytes = bincode::serialize(&vec![0x14_u8, 0x8b]).unwrap();
+        assert!(bincode::deserialize::<Hll>(&bytes).is_err());
+    }
+}
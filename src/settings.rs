@@ -1,5 +1,6 @@
 use thiserror::Error;
 
+use crate::math;
 use crate::utils::divide_by_8_round_up;
 
 // minimum and maximum values for the log-base-2 of the number of registers
@@ -16,7 +17,7 @@ const MAXIMUM_REG_WIDTH_PARAM: u32 = 8;
 // implementation's constructor and parameter names
 const MINIMUM_EXPTHRESH_PARAM: i32 = -1;
 const MAXIMUM_EXPTHRESH_PARAM: i32 = 18;
-const MAXIMUM_EXPLICIT_THRESHOLD: u32 = 1 << (MAXIMUM_EXPTHRESH_PARAM - 1); /*per storage spec*/
+pub(crate) const MAXIMUM_EXPLICIT_THRESHOLD: u32 = 1 << (MAXIMUM_EXPTHRESH_PARAM - 1); /*per storage spec*/
 
 // AutoExplicitThreshold indicates that the threshold at which an Hll goes
 // from using an explicit to a probabalistic representation should be
@@ -26,6 +27,10 @@ const MAXIMUM_EXPLICIT_THRESHOLD: u32 = 1 << (MAXIMUM_EXPTHRESH_PARAM - 1); /*pe
 // it may be desirable to use the same explicit threshold.
 const AUTO_EXPLICIT_THRESHOLD: i32 = -1;
 
+// number of bytes written by `Settings::to_bytes`.  These are the same as bytes 1-2 of a serialized
+// Hll header.
+const SERIALIZED_SETTINGS_LEN: usize = 2;
+
 /// Settings are used to configure the Hll and how it transitions between the
 /// backing storage types.
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -49,6 +54,8 @@ pub struct Settings {
     /// SparseEnabled controls whether the Hll will use the sparse
     /// representation.  The thresholds for conversion are automatically
     /// calculated by the library when this field is set to true (recommended).
+    /// Sparse entries are 32 bits wide, so it stays disabled when log_2m plus
+    /// reg_width is more than 32.
     pub(crate) sparse_threshold: Option<i32>,
 
     /// pw_max_mask is a mask that prevents overflow of HyperLogLog registers.
@@ -67,6 +74,25 @@ pub struct Settings {
     /// "large" range cardinality correction formula
     pub(crate) large_estimator_cutoff: f64,
     pub(crate) two_to_l: f64,
+
+    /// bias_correction enables the HyperLogLog++ empirical bias correction for small cardinalities.
+    /// It is not part of the storage spec so it is never serialized.
+    pub(crate) bias_correction: bool,
+}
+
+/// SettingsPreset names commonly used combinations of log_2m and reg_width, see
+/// `Settings::from_preset`.  The sizes are those of the dense representation, smaller sets use
+/// less memory while they are stored explicitly or sparsely.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SettingsPreset {
+    /// log_2m 10 and reg_width 5: 640 bytes with a standard error of about 3.25%.
+    Compact,
+    /// log_2m 11 and reg_width 5: 1,280 bytes with a standard error of about 2.3%.  These are the
+    /// `SettingsBuilder` defaults.
+    Balanced,
+    /// log_2m 14 and reg_width 6: 12,288 bytes with a standard error of about 0.81%.  This matches
+    /// the Redis HyperLogLog layout.
+    HighPrecision,
 }
 
 #[derive(Clone, Debug, Error)]
@@ -75,10 +101,18 @@ pub enum SettingsError {
     Log2m,
     #[error("reg_width must be between {MINIMUM_REG_WIDTH_PARAM}, {MAXIMUM_REG_WIDTH_PARAM}")]
     RegWidth,
-    #[error("Threshold must be between {MINIMUM_EXPTHRESH_PARAM}, {MAXIMUM_EXPTHRESH_PARAM}")]
+    #[error(
+        "explicit_threshold must be {AUTO_EXPLICIT_THRESHOLD}, 0 or between 1, {MAXIMUM_EXPLICIT_THRESHOLD}"
+    )]
     Threshold,
+    #[error("sparse_threshold must be at least 1 and less than the number of registers, found {0}")]
+    SparseThreshold(i32),
+    #[error("sparse entries of {0} bits don't fit in 32 bits")]
+    SparseEntryWidth(u32),
     #[error("config mismatch. log_2m and reg_width must match when combining hll's")]
     MisMatch,
+    #[error("serialized settings require {SERIALIZED_SETTINGS_LEN} bytes, found {0}")]
+    Length(usize),
 }
 
 impl Settings {
@@ -88,32 +122,89 @@ impl Settings {
         explicit_threshold: i32,
         sparse_enabled: bool,
     ) -> Result<Self, SettingsError> {
-        let sparse_threshold = match sparse_enabled {
+        let settings = Self::build(log_2m, reg_width, explicit_threshold, sparse_enabled);
+        settings.check(true)?;
+
+        Ok(settings)
+    }
+
+    /// new_with_large_threshold is like `new` but accepts explicit thresholds above the storage
+    /// spec maximum of 131,072.  This is meant for interop with data sets and implementations that
+    /// don't enforce the maximum.
+    pub fn new_with_large_threshold(
+        log_2m: u32,
+        reg_width: u32,
+        explicit_threshold: i32,
+        sparse_enabled: bool,
+    ) -> Result<Self, SettingsError> {
+        let settings = Self::build(log_2m, reg_width, explicit_threshold, sparse_enabled);
+        settings.check(false)?;
+
+        Ok(settings)
+    }
+
+    /// for_error_rate returns settings with the smallest log_2m whose standard error,
+    /// 1.04 / sqrt(2^log_2m), is at most target_error.  The explicit threshold is calculated
+    /// automatically.  Targets below the error of the largest log_2m of 31 (about 0.002%) can't be
+    /// reached and return `SettingsError::Log2m`.
+    pub fn for_error_rate(
+        target_error: f64,
+        reg_width: u32,
+        sparse_enabled: bool,
+    ) -> Result<Self, SettingsError> {
+        let log_2m = (MINIMUM_LOG_2M_PARAM..=MAXIMUM_LOG_2M_PARAM)
+            .find(|log_2m| Self::std_error(*log_2m) <= target_error)
+            .ok_or(SettingsError::Log2m)?;
+
+        Self::new(log_2m, reg_width, AUTO_EXPLICIT_THRESHOLD, sparse_enabled)
+    }
+
+    /// from_preset returns the settings for a preset with an automatically calculated explicit
+    /// threshold and sparse storage enabled.
+    pub fn from_preset(preset: SettingsPreset) -> Self {
+        let (log_2m, reg_width) = match preset {
+            SettingsPreset::Compact => (10, 5),
+            SettingsPreset::Balanced => (11, 5),
+            SettingsPreset::HighPrecision => (14, 6),
+        };
+
+        Self::new(log_2m, reg_width, AUTO_EXPLICIT_THRESHOLD, true).expect("presets are valid")
+    }
+
+    fn build(log_2m: u32, reg_width: u32, explicit_threshold: i32, sparse_enabled: bool) -> Self {
+        // sparse entries are serialized as u32, which is too narrow for the largest settings
+        let sparse_threshold = match sparse_enabled && log_2m + reg_width <= u32::BITS {
             true => Some(Self::calculate_sparse_threshold(log_2m, reg_width)),
             false => None,
         };
 
-        let settings = Self {
+        Self {
             log_2m,
             reg_width,
             explicit_threshold,
             sparse_threshold,
             pw_max_mask: Settings::pw_max_mask(reg_width),
-            m_bits_mask: ((1 << log_2m) - 1),
+            m_bits_mask: (1_u64 << log_2m) - 1,
             alpha_msquared: Settings::alpha_m_squared(log_2m),
-            small_estimator_cutoff: Settings::small_estimator_cutoff(1 << log_2m),
+            small_estimator_cutoff: Settings::small_estimator_cutoff(1_u32 << log_2m),
             large_estimator_cutoff: Settings::large_estimator_cutoff(Settings::two_to_l(
                 log_2m, reg_width,
             )),
             two_to_l: Settings::two_to_l(log_2m, reg_width),
-        };
-
-        settings.validate()?;
-
-        Ok(settings)
+            bias_correction: false,
+        }
     }
 
+    /// validate checks that the settings are in range.  Explicit thresholds above the storage spec
+    /// maximum are accepted, since `new_with_large_threshold` and `Hll::from_bytes` both produce
+    /// them.
     pub fn validate(&self) -> Result<(), SettingsError> {
+        self.check(false)
+    }
+
+    /// check validates the settings.  The upper bound of the explicit threshold is only enforced if
+    /// bounded_threshold is set.
+    fn check(&self, bounded_threshold: bool) -> Result<(), SettingsError> {
         if !(MINIMUM_LOG_2M_PARAM..=MAXIMUM_LOG_2M_PARAM).contains(&self.log_2m) {
             return Err(SettingsError::Log2m);
         }
@@ -122,14 +213,107 @@ impl Settings {
             return Err(SettingsError::RegWidth);
         }
 
-        // the unit tests are using 256 for their threshold which is larger than what the constructor in golang allows.
-        // if !(MINIMUM_EXPTHRESH_PARAM..=MAXIMUM_EXPTHRESH_PARAM).contains(&self.explicit_threshold) {
-        //     return Err(SettingsError::Threshold);
-        // }
+        let maximum_threshold = match bounded_threshold {
+            true => MAXIMUM_EXPLICIT_THRESHOLD as i32,
+            false => i32::MAX,
+        };
+        if !(MINIMUM_EXPTHRESH_PARAM..=maximum_threshold).contains(&self.explicit_threshold) {
+            return Err(SettingsError::Threshold);
+        }
 
         Ok(())
     }
 
+    /// with_bias_correction returns these settings with the HyperLogLog++ bias correction enabled
+    /// or disabled.  When enabled, estimates below 5m (m being the number of registers) are
+    /// corrected with empirically measured bias tables and linear counting is used only below the
+    /// thresholds published by Heule et al.  This reduces the error between the small and large
+    /// ranges.  The tables cover log_2m 4 to 18 and registers of at least 4 bits, other settings
+    /// use the standard estimator.
+    ///
+    /// The bias tables are not the ones published by Heule et al.  They were measured with this
+    /// crate's estimator using the method of the paper (see `examples/bias_tables.rs`), so
+    /// corrected estimates are close to, but not the same as, those of HyperLogLog++
+    /// implementations that ship the published tables.  The registers and serialized bytes are
+    /// unaffected, only `cardinality` differs.
+    pub fn with_bias_correction(mut self, enabled: bool) -> Self {
+        self.bias_correction = enabled;
+        self
+    }
+
+    /// with_sparse_threshold enables sparse storage and overrides the calculated number of registers
+    /// it holds before converting to dense storage, for example to match another implementation.
+    /// The threshold must be positive and less than the number of registers, and log_2m plus
+    /// reg_width can't be more than 32.
+    ///
+    /// The storage spec only records whether sparse storage is enabled, so `from_bytes` calculates
+    /// the threshold again.  Apply the override again after deserializing.
+    pub fn with_sparse_threshold(mut self, threshold: i32) -> Result<Self, SettingsError> {
+        if threshold < 1 || threshold as u32 >= self.registers() {
+            return Err(SettingsError::SparseThreshold(threshold));
+        }
+
+        let entry_width = self.log_2m + self.reg_width;
+        if entry_width > u32::BITS {
+            return Err(SettingsError::SparseEntryWidth(entry_width));
+        }
+
+        self.sparse_threshold = Some(threshold);
+        Ok(self)
+    }
+
+    /// registers returns the number of registers, 2^log_2m.
+    pub fn registers(&self) -> u32 {
+        1 << self.log_2m
+    }
+
+    /// dense_serialized_len returns the size of a Dense Hll serialized with `Hll::to_bytes`: the 3
+    /// byte header followed by the bit packed registers.
+    ///
+    /// ```
+    /// use rust_hll::Settings;
+    ///
+    /// let settings = Settings::new(14, 6, -1, true).unwrap();
+    /// assert_eq!(3 + 12_288, settings.dense_serialized_len());
+    /// ```
+    pub fn dense_serialized_len(&self) -> usize {
+        crate::HEADER_LEN + self.dense_bytes_size()
+    }
+
+    /// expected_std_error returns the relative standard error of estimates once the Hll uses
+    /// registers, 1.04 / sqrt(2^log_2m).  Roughly 68% of estimates fall within one standard error
+    /// of the true cardinality and 95% within two.
+    ///
+    /// ```
+    /// use rust_hll::Settings;
+    ///
+    /// let settings = Settings::new(14, 5, -1, true).unwrap();
+    /// assert_eq!(16384, settings.registers());
+    /// assert!((settings.expected_std_error() - 0.0081).abs() < 0.0001);
+    /// ```
+    pub fn expected_std_error(&self) -> f64 {
+        Self::std_error(self.log_2m)
+    }
+
+    /// max_representable_cardinality returns 2^L, the size of the hash space the registers can
+    /// describe.  Estimates above 1/30th of it are extrapolated by the large range correction,
+    /// which grows less accurate as the count approaches 2^L and saturates beyond it, so the
+    /// settings should keep expected cardinalities well below this.
+    ///
+    /// ```
+    /// use rust_hll::Settings;
+    ///
+    /// let settings = Settings::new(14, 5, -1, true).unwrap();
+    /// assert_eq!(2_f64.powi(44), settings.max_representable_cardinality());
+    /// ```
+    pub fn max_representable_cardinality(&self) -> f64 {
+        self.two_to_l
+    }
+
+    fn std_error(log_2m: u32) -> f64 {
+        1.04 / math::sqrt((1_u64 << log_2m) as f64)
+    }
+
     pub fn settings_check(&self, other: &Self) -> Result<(), SettingsError> {
         if self.log_2m == other.log_2m && self.reg_width == other.reg_width {
             return Ok(());
@@ -147,29 +331,57 @@ impl Settings {
         }
     }
 
+    /// widening_check succeeds if the registers of other can be merged into registers with these
+    /// settings: log_2m must match and other's registers can't be wider than ours.
+    pub(crate) fn widening_check(&self, other: &Self) -> Result<(), SettingsError> {
+        if self.log_2m == other.log_2m && self.reg_width >= other.reg_width {
+            return Ok(());
+        }
+
+        Err(SettingsError::MisMatch)
+    }
+
+    /// max_register_value is the largest value that fits in a register.
+    pub(crate) fn max_register_value(&self) -> u8 {
+        ((1_u32 << self.reg_width) - 1) as u8
+    }
+
+    /// max_hashed_register_value is the largest value `add_raw` can set.  Registers see the
+    /// 64 - log_2m hash bits above the register index, so for wide registers this is below
+    /// `max_register_value` and larger values can only come from corrupt input.
+    pub(crate) fn max_hashed_register_value(&self) -> u8 {
+        self.max_register_value()
+            .min((u64::BITS - self.log_2m) as u8)
+    }
+
+    /// dense_bytes_size is the number of bytes needed to hold every register.
+    pub(crate) fn dense_bytes_size(&self) -> usize {
+        divide_by_8_round_up((1_usize << self.log_2m) * self.reg_width as usize)
+    }
+
     /// determines a good cutoff to switch between explicit and probabilistic storage.
     pub fn calculate_explicit_threshold(log_2m: u32, reg_width: u32) -> u32 {
         // NOTE:  This math matches the size calculation in the PostgreSQL impl.
-        let m = 1 << log_2m;
-        let full_representation_size = divide_by_8_round_up(reg_width * m); /*round up to next whole byte*/
+        let m = 1_usize << log_2m;
+        let full_representation_size = divide_by_8_round_up(reg_width as usize * m); /*round up to next whole byte*/
         let num_longs = full_representation_size / 8;
 
-        if num_longs > MAXIMUM_EXPLICIT_THRESHOLD {
+        if num_longs > MAXIMUM_EXPLICIT_THRESHOLD as usize {
             return MAXIMUM_EXPLICIT_THRESHOLD;
         }
 
-        num_longs
+        num_longs as u32
     }
 
     // calculate_sparse_threshold determines a good cutoff to switch between sparse
     // and dense probabilistic storage.
     fn calculate_sparse_threshold(log_2m: u32, reg_width: u32) -> i32 {
-        let m = 1 << log_2m;
         let short_word_length: f64 = (log_2m + reg_width).into();
 
-        let reg_bits: f64 = (m * reg_width).into();
+        // m * reg_width overflows u32 for the largest log_2m
+        let reg_bits = ((1_u64 << log_2m) * reg_width as u64) as f64;
 
-        let largest_pow2_less_than_cutoff: u32 = (reg_bits / short_word_length).log2() as u32;
+        let largest_pow2_less_than_cutoff: u32 = math::log2(reg_bits / short_word_length) as u32;
 
         1 << largest_pow2_less_than_cutoff
     }
@@ -185,7 +397,8 @@ impl Settings {
     /// registerCount squared where gamma is based on the value of registerCount)
     /// used by the HyperLogLog algorithm.
     pub(crate) fn alpha_m_squared(log_2m: u32) -> f64 {
-        let m: f64 = (1 << log_2m).into();
+        // 1 << 31 as an i32 is negative
+        let m: f64 = (1_u32 << log_2m).into();
 
         match log_2m {
             4 => 0.673 * m * m,
@@ -212,25 +425,69 @@ impl Settings {
     // two_to_l calculates 2 raised to L where L is the "large range correction
     // boundary" described at http://research.neustar.biz/2013/01/24/hyperloglog-googles-take-on-engineering-hll.
     pub(crate) fn two_to_l(log_2m: u32, reg_width: u32) -> f64 {
-        let max_register_value = (1 << reg_width) - 1;
+        let max_register_value = (1_u64 << reg_width) - 1;
 
         // Since 1 is added to p(w) in the insertion algorithm, only
         // (max_register_value - 1) bits are inspected hence the hash
-        // space is one power of two smaller.
-        let pw_bits = max_register_value - 1;
-        let total_bits = pw_bits + log_2m;
+        // space is one power of two smaller.  For reg_width = 1 that leaves
+        // no bits and 2^L is just m.
+        let pw_bits = max_register_value.saturating_sub(1);
+        let total_bits = pw_bits + log_2m as u64;
+
+        // NOTE : this can get larger than fits in a 64 bit integer, up to 2^285 for reg_width = 8
+        // and log_2m = 31, which is still well within f64.
+        math::powf(2.0, total_bits as f64)
+    }
 
-        // NOTE : this can get larger than fits in a 64 bit integer.
-        2_f64.powf(total_bits.into())
+    /// to_bytes serializes the settings as the parameter and cutoff bytes of the storage spec header.
+    pub fn to_bytes(&self) -> [u8; SERIALIZED_SETTINGS_LEN] {
+        [
+            (((self.reg_width - 1) << 5) | self.log_2m) as u8,
+            self.pack_cutoff_byte(),
+        ]
+    }
+
+    /// serialized_header_bytes returns the storage spec header of an Hll of the given storage type
+    /// with these settings, the version/type byte followed by the settings bytes.
+    pub fn serialized_header_bytes(&self, type_id: u8) -> [u8; crate::HEADER_LEN] {
+        let [params, cutoff] = self.to_bytes();
+        [
+            (crate::SERIALIZATION_VERSION << 4) | type_id,
+            params,
+            cutoff,
+        ]
+    }
+
+    /// from_bytes parses settings written by `to_bytes`.  Only the first two bytes of buf are read.
+    /// Explicit thresholds above the storage spec maximum are accepted, as with
+    /// `new_with_large_threshold`.
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, SettingsError> {
+        if buf.len() < SERIALIZED_SETTINGS_LEN {
+            return Err(SettingsError::Length(buf.len()));
+        }
+
+        let reg_width = (buf[0] >> 5) + 1;
+        let log_2m = buf[0] & 0x1F;
+        let (sparse_enabled, explicit_threshold) = Self::unpack_cutoff_byte(buf[1]);
+
+        Self::new_with_large_threshold(
+            log_2m as u32,
+            reg_width as u32,
+            explicit_threshold,
+            sparse_enabled,
+        )
     }
 
+    /// pack_cutoff_byte encodes the sparse flag and explicit threshold as described by the storage
+    /// spec.  A positive threshold is stored as log2(threshold) + 1, so thresholds that aren't a
+    /// power of two are rounded down to the previous power of two, e.g. 850 is read back as 512.
     pub(crate) fn pack_cutoff_byte(&self) -> u8 {
         let threshold = if self.explicit_threshold == AUTO_EXPLICIT_THRESHOLD {
             63
         } else if self.explicit_threshold == 0 {
             0
         } else {
-            u32::BITS - (self.explicit_threshold as u32).leading_zeros() - 1
+            u32::BITS - (self.explicit_threshold as u32).leading_zeros()
         };
 
         let mut res = threshold;
@@ -243,7 +500,8 @@ impl Settings {
 
     /// (sparse_enabled, explicit_threshold)
     pub(crate) fn unpack_cutoff_byte(b: u8) -> (bool, i32) {
-        let sparse_enabled = b >> 6 == 1;
+        // the top bit is unused by the settings
+        let sparse_enabled = (b >> 6) & 1 == 1;
         let threshold = b & 0x3F;
 
         if threshold == 0 {
@@ -254,13 +512,16 @@ impl Settings {
             return (sparse_enabled, -1);
         }
 
-        (sparse_enabled, 1 << (threshold - 1))
+        // thresholds past the spec maximum saturate rather than overflow
+        let threshold = (1_i64 << (threshold - 1)).min(i32::MAX as i64);
+
+        (sparse_enabled, threshold as i32)
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::Settings;
+    use super::{Settings, SettingsError, SettingsPreset};
 
     #[test]
     fn pw() {
@@ -269,6 +530,190 @@ mod test {
         println!("{:?}", settings);
     }
 
+    #[test]
+    fn to_from_bytes() {
+        for log_2m in [4, 11, 16] {
+            for reg_width in [1, 5, 8] {
+                for explicit_threshold in [-1, 0] {
+                    for sparse_enabled in [true, false] {
+                        let settings =
+                            Settings::new(log_2m, reg_width, explicit_threshold, sparse_enabled)
+                                .unwrap();
+                        let bytes = settings.to_bytes();
+
+                        assert_eq!(settings, Settings::from_bytes(&bytes).unwrap());
+                    }
+                }
+            }
+        }
+
+        assert!(matches!(
+            Settings::from_bytes(&[0x8b]),
+            Err(SettingsError::Length(1))
+        ));
+    }
+
+    #[test]
+    fn explicit_threshold_range() {
+        for explicit_threshold in [-1, 0, 1, 256, 131_072] {
+            assert!(Settings::new(11, 5, explicit_threshold, true).is_ok());
+        }
+
+        for explicit_threshold in [-2, i32::MIN, 131_073, 1_000_000] {
+            assert!(matches!(
+                Settings::new(11, 5, explicit_threshold, true),
+                Err(SettingsError::Threshold)
+            ));
+        }
+
+        let settings = Settings::new_with_large_threshold(11, 5, 1_000_000, true).unwrap();
+        assert_eq!(1_000_000, settings.explicit_threshold());
+        assert!(settings.validate().is_ok());
+        assert!(matches!(
+            Settings::new_with_large_threshold(11, 5, -2, true),
+            Err(SettingsError::Threshold)
+        ));
+    }
+
+    #[test]
+    fn explicit_threshold_round_trip() {
+        for (explicit_threshold, expected) in [
+            (-1, -1),
+            (0, 0),
+            (1, 1),
+            (2, 2),
+            (3, 2),
+            (256, 256),
+            (850, 512),
+            (1023, 512),
+            (1024, 1024),
+            (131_072, 131_072),
+        ] {
+            let settings = Settings::new(11, 5, explicit_threshold, true).unwrap();
+            let settings = Settings::from_bytes(&settings.to_bytes()).unwrap();
+            assert_eq!(
+                expected, settings.explicit_threshold,
+                "{}",
+                explicit_threshold
+            );
+        }
+
+        let settings = Settings::new_with_large_threshold(11, 5, i32::MAX, true).unwrap();
+        let settings = Settings::from_bytes(&settings.to_bytes()).unwrap();
+        assert_eq!(1 << 30, settings.explicit_threshold);
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn for_error_rate() {
+        let settings = Settings::for_error_rate(0.01, 5, true).unwrap();
+        assert_eq!(14, settings.log_2m);
+        assert_eq!(5, settings.reg_width);
+        assert_eq!(-1, settings.explicit_threshold);
+
+        assert_eq!(7, Settings::for_error_rate(0.1, 5, true).unwrap().log_2m);
+        assert_eq!(4, Settings::for_error_rate(1.0, 5, true).unwrap().log_2m);
+
+        for target_error in [0.0, -0.1, 1e-6, f64::NAN] {
+            assert!(matches!(
+                Settings::for_error_rate(target_error, 5, true),
+                Err(SettingsError::Log2m)
+            ));
+        }
+        assert!(matches!(
+            Settings::for_error_rate(0.01, 9, true),
+            Err(SettingsError::RegWidth)
+        ));
+    }
+
+    #[test]
+    fn from_preset() {
+        for (preset, bytes, error) in [
+            (SettingsPreset::Compact, 640, 0.0325),
+            (SettingsPreset::Balanced, 1280, 0.023),
+            (SettingsPreset::HighPrecision, 12_288, 0.0081),
+        ] {
+            let settings = Settings::from_preset(preset);
+            assert!(settings.validate().is_ok());
+            assert_eq!(bytes, settings.dense_bytes_size());
+            assert!(
+                (settings.expected_std_error() - error).abs() < 0.0005,
+                "{:?}",
+                preset
+            );
+        }
+    }
+
+    #[test]
+    fn sparse_entry_width() {
+        for (log_2m, reg_width, sparse_enabled) in [(31, 1, true), (24, 8, true), (31, 2, false)] {
+            let settings = Settings::new(log_2m, reg_width, -1, true).unwrap();
+            assert_eq!(sparse_enabled, settings.sparse_threshold.is_some());
+            assert_eq!(
+                settings,
+                Settings::from_bytes(&settings.to_bytes()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn with_sparse_threshold() {
+        let settings = Settings::new(10, 4, -1, false).unwrap();
+        for threshold in [0, -1, 1024] {
+            assert!(matches!(
+                settings.with_sparse_threshold(threshold),
+                Err(SettingsError::SparseThreshold(t)) if t == threshold
+            ));
+        }
+
+        assert!(matches!(
+            Settings::new(25, 8, -1, false)
+                .unwrap()
+                .with_sparse_threshold(850),
+            Err(SettingsError::SparseEntryWidth(33))
+        ));
+
+        let overridden = settings.with_sparse_threshold(850).unwrap();
+        assert_eq!(Some(850), overridden.sparse_threshold);
+        assert!(overridden.validate().is_ok());
+
+        // only the sparse flag is serialized, the threshold is calculated again
+        let deserialized = Settings::from_bytes(&overridden.to_bytes()).unwrap();
+        assert_eq!(
+            Settings::new(10, 4, -1, true).unwrap().sparse_threshold,
+            deserialized.sparse_threshold
+        );
+        assert_eq!(overridden, deserialized.with_sparse_threshold(850).unwrap());
+    }
+
+    #[test]
+    fn two_to_l() {
+        for (log_2m, reg_width, total_bits) in [(4, 1, 4), (31, 1, 31), (4, 8, 258), (31, 8, 285)] {
+            let two_to_l = Settings::two_to_l(log_2m, reg_width);
+            assert!(two_to_l.is_finite());
+            assert_eq!(2_f64.powi(total_bits), two_to_l);
+        }
+    }
+
+    #[test]
+    fn max_log_2m() {
+        let m = 2_f64.powi(31);
+        for reg_width in 1..=8 {
+            let settings = Settings::new(31, reg_width, -1, false).unwrap();
+            assert_eq!(u32::MAX as u64 >> 1, settings.m_bits_mask);
+            assert_eq!(1 << 31, settings.registers());
+            assert_eq!(
+                (1 << 31) / 8 * reg_width as usize + crate::HEADER_LEN,
+                settings.dense_serialized_len()
+            );
+            assert_eq!(m * 5.0 / 2.0, settings.small_estimator_cutoff);
+            assert_eq!(
+                (0.7213 / (1.0 + 1.079 / m)) * m * m,
+                settings.alpha_msquared
+            );
+        }
+    }
+
     #[test]
     fn left_shift() {
         assert_eq!(1 << 0, 1);
@@ -0,0 +1,93 @@
+use alloc::vec::Vec;
+
+use crate::{Hll, Settings, SettingsError};
+
+/// simulate_error estimates the accuracy of an Hll configuration.  For each true cardinality it
+/// builds `trials` Hlls from pseudorandom hashed values and returns the mean relative error
+/// (|estimate - actual| / actual) observed across the trials.  A cardinality of 0 has no relative
+/// error, so its error is the absolute estimate instead.  The same seed always produces the same
+/// results.
+///
+/// Explicit and sparse storage use their automatically calculated thresholds, so cardinalities
+/// below the explicit threshold report no error.
+pub fn simulate_error(
+    log_2m: u32,
+    reg_width: u32,
+    cardinalities: &[u64],
+    trials: usize,
+    seed: u64,
+) -> Result<Vec<(u64, f64)>, SettingsError> {
+    let settings = Settings::new(log_2m, reg_width, -1, true)?;
+    let mut rng = SplitMix64(seed);
+
+    let res = cardinalities
+        .iter()
+        .map(|cardinality| {
+            let mut total_error = 0.0;
+
+            for _ in 0..trials {
+                let mut hll = Hll::new(settings);
+                for _ in 0..*cardinality {
+                    hll.add_raw(rng.next());
+                }
+
+                let actual = *cardinality as f64;
+                let error = (hll.cardinality() as f64 - actual).abs();
+                total_error += match *cardinality {
+                    0 => error,
+                    _ => error / actual,
+                };
+            }
+
+            (*cardinality, total_error / trials as f64)
+        })
+        .collect();
+
+    Ok(res)
+}
+
+/// SplitMix64 is a small, fast pseudorandom generator whose output is well enough mixed to stand in
+/// for hashed values.  See https://prng.di.unimi.it/splitmix64.c.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::simulate_error;
+
+    #[test]
+    fn error_decreases_with_log_2m() {
+        let cardinalities = [5_000, 20_000];
+
+        let coarse = simulate_error(6, 5, &cardinalities, 20, 1).unwrap();
+        let fine = simulate_error(12, 5, &cardinalities, 20, 1).unwrap();
+
+        for ((cardinality, coarse), (_, fine)) in coarse.iter().zip(fine.iter()) {
+            assert!(
+                fine < coarse,
+                "cardinality {}: log_2m 12 error {} not below log_2m 6 error {}",
+                cardinality,
+                fine,
+                coarse
+            );
+        }
+
+        assert_eq!(coarse, simulate_error(6, 5, &cardinalities, 20, 1).unwrap());
+    }
+
+    #[test]
+    fn explicit_is_exact() {
+        let res = simulate_error(11, 5, &[0, 1, 10, 100], 5, 1).unwrap();
+        assert_eq!(vec![(0, 0.0), (1, 0.0), (10, 0.0), (100, 0.0)], res);
+    }
+}
@@ -0,0 +1,122 @@
+use std::io;
+
+use crate::Hll;
+
+/// `HllSink` adds delimited records written to it to an Hll, e.g. the lines of a file with
+/// `io::copy`.  Each record is added with `Hll::add_bytes`, without its delimiter.
+///
+/// A record may be split across any number of `write` calls: bytes after the last delimiter are
+/// buffered until the delimiter arrives.  `flush` does not add the buffered bytes since more of the
+/// record may follow, they are only added as a final record when the sink is finished or dropped.
+/// Empty records between two delimiters are added like any other record, but nothing is added for
+/// the empty remainder after a trailing delimiter.
+pub struct HllSink<'a> {
+    hll: &'a mut Hll,
+    delimiter: u8,
+    record: Vec<u8>,
+}
+
+impl<'a> HllSink<'a> {
+    /// new returns a sink that adds newline delimited records to hll.
+    pub fn new(hll: &'a mut Hll) -> Self {
+        Self::with_delimiter(hll, b'\n')
+    }
+
+    pub fn with_delimiter(hll: &'a mut Hll, delimiter: u8) -> Self {
+        Self {
+            hll,
+            delimiter,
+            record: Vec::new(),
+        }
+    }
+
+    /// finish adds the buffered final record, if any.  This is the same as dropping the sink.
+    pub fn finish(self) {}
+}
+
+impl io::Write for HllSink<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut records = buf.split(|b| *b == self.delimiter);
+        // split always yields the bytes after the last delimiter, even if there are none
+        let last = records.next_back().unwrap();
+
+        for record in records {
+            if self.record.is_empty() {
+                self.hll.add_bytes(record);
+            } else {
+                self.record.extend_from_slice(record);
+                self.hll.add_bytes(&self.record);
+                self.record.clear();
+            }
+        }
+
+        self.record.extend_from_slice(last);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for HllSink<'_> {
+    fn drop(&mut self) {
+        if !self.record.is_empty() {
+            self.hll.add_bytes(&self.record);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{self, Write};
+
+    use super::HllSink;
+    use crate::{Hll, Settings};
+
+    #[test]
+    fn distinct_lines() {
+        let settings = Settings::new(11, 5, -1, true).unwrap();
+        let lines: Vec<String> = (0..100).map(|i| format!("line {i}")).collect();
+        let stream = vec![lines.join("\n"); 3].join("\n");
+
+        let mut expected = Hll::new(settings);
+        for line in lines.iter() {
+            expected.add_bytes(line.as_bytes());
+        }
+
+        // records split across writes of every size
+        for chunk_size in [1, 2, 7, 64, stream.len()] {
+            let mut hll = Hll::new(settings);
+            let mut sink = HllSink::new(&mut hll);
+            for chunk in stream.as_bytes().chunks(chunk_size) {
+                sink.write_all(chunk).unwrap();
+            }
+            sink.flush().unwrap();
+            sink.finish();
+
+            assert_eq!(expected, hll);
+            assert_eq!(100, hll.cardinality());
+        }
+
+        let mut hll = Hll::new(settings);
+        io::copy(&mut stream.as_bytes(), &mut HllSink::new(&mut hll)).unwrap();
+        assert_eq!(expected, hll);
+    }
+
+    #[test]
+    fn delimiters() {
+        let settings = Settings::new(11, 5, -1, true).unwrap();
+
+        let mut hll = Hll::new(settings);
+        HllSink::with_delimiter(&mut hll, b',')
+            .write_all(b"a,b,,a,")
+            .unwrap();
+
+        let mut expected = Hll::new(settings);
+        for record in [&b"a"[..], b"b", b""] {
+            expected.add_bytes(record);
+        }
+        assert_eq!(expected, hll);
+    }
+}
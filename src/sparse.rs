@@ -1,16 +1,36 @@
-use std::collections::BTreeMap;
-use std::collections::btree_map::{Entry, Iter};
+#[cfg(feature = "touch-counts")]
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::slice::Iter;
 
 use crate::dense::DenseRegisters;
 use crate::explicit::ExplicitStorage;
 use crate::settings::Settings;
-use crate::utils::{calc_position, divide_by_8_round_up, read_bits, write_bits};
-use crate::{Registers, Storage};
+use crate::utils::{calc_position, divide_by_8_round_up, inverse_pow2, try_read_bits, write_bits};
+use crate::{HllError, RegisterIndex, RegisterValue, Registers, Storage};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct SparseRegisters {
     pub(crate) settings: Settings,
-    pub(crate) buf: BTreeMap<u32, u8>,
+    /// (reg_num, value) pairs sorted by reg_num.  The sparse representation is only used while
+    /// small, where a sorted vec beats a tree on memory and cache behavior.
+    pub(crate) buf: Vec<(u32, u8)>,
+    #[cfg(feature = "touch-counts")]
+    touches: TouchCounts,
+}
+
+/// TouchCounts tracks how many times each register was the target of a `set` call, like the dense
+/// touch counts, but only for registers that were touched.  They are carried over to dense storage
+/// and ignored when comparing registers.
+#[cfg(feature = "touch-counts")]
+#[derive(Debug, Clone, Default)]
+struct TouchCounts(BTreeMap<u32, u32>);
+
+#[cfg(feature = "touch-counts")]
+impl PartialEq for TouchCounts {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
 }
 
 impl SparseRegisters {
@@ -18,6 +38,8 @@ impl SparseRegisters {
         Self {
             settings: *settings,
             buf: Default::default(),
+            #[cfg(feature = "touch-counts")]
+            touches: Default::default(),
         }
     }
 
@@ -25,6 +47,8 @@ impl SparseRegisters {
         Self {
             settings: *settings,
             buf: self.buf.clone(),
+            #[cfg(feature = "touch-counts")]
+            touches: self.touches.clone(),
         }
     }
 
@@ -39,53 +63,135 @@ impl SparseRegisters {
         self.buf.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.buf.shrink_to_fit();
+    }
+
+    /// heap_size returns the number of bytes allocated for the registers.
+    pub fn heap_size(&self) -> usize {
+        self.buf.capacity() * size_of::<(u32, u8)>()
+    }
+
     pub fn to_dense(&self, settings: Option<&Settings>) -> DenseRegisters {
         let mut registers = DenseRegisters::with_settings(settings.unwrap_or(&self.settings));
         for (reg_num, value) in self.buf.iter() {
             registers.set_reg(*reg_num, *value);
         }
+        #[cfg(feature = "touch-counts")]
+        registers.add_touch_counts(
+            self.touches
+                .0
+                .iter()
+                .map(|(reg_num, count)| (*reg_num, *count)),
+        );
 
         registers
     }
 
-    pub fn union_sparse(&mut self, other: &Self) {
-        for (reg_num, value) in other.buf.iter() {
-            self.set_if_greater(*reg_num, *value);
-        }
+    /// get returns the value of register reg_num if it is set.
+    pub fn get(&self, reg_num: u32) -> Option<u8> {
+        self.buf
+            .binary_search_by_key(&reg_num, |(k, _)| *k)
+            .ok()
+            .map(|i| self.buf[i].1)
     }
 
-    pub fn union_explicit(&mut self, explicit_storage: &ExplicitStorage) {
-        for i in explicit_storage.iter() {
-            self.set(i);
+    /// union_sparse returns the number of registers that were raised.  Values too large for our
+    /// registers are clamped to the max register value.
+    pub fn union_sparse(&mut self, other: &Self) -> u32 {
+        let mask = self.settings.m_bits_mask as u8;
+        let max_value = self.settings.max_register_value();
+        let mut merged = Vec::with_capacity(self.buf.len() + other.buf.len());
+        let mut changed = 0;
+
+        // merge join of the two sorted register lists
+        let mut lhs = self.buf.iter().peekable();
+        let mut rhs = other
+            .buf
+            .iter()
+            .map(|(k, v)| (*k, (*v & mask).min(max_value)))
+            .peekable();
+        loop {
+            let next = match (lhs.peek(), rhs.peek()) {
+                (Some(l), Some(r)) if l.0 < r.0 => *lhs.next().unwrap(),
+                (Some(l), Some(r)) if l.0 > r.0 => {
+                    changed += 1;
+                    rhs.next().unwrap()
+                }
+                (Some(_), Some(_)) => {
+                    let (reg_num, value) = *lhs.next().unwrap();
+                    let (_, other_value) = rhs.next().unwrap();
+                    if other_value > value {
+                        changed += 1;
+                    }
+                    (reg_num, value.max(other_value))
+                }
+                (Some(_), None) => *lhs.next().unwrap(),
+                (None, Some(_)) => {
+                    changed += 1;
+                    rhs.next().unwrap()
+                }
+                (None, None) => break,
+            };
+
+            merged.push(next);
         }
+
+        self.buf = merged;
+        changed
     }
 
-    pub fn iter(&self) -> Iter<'_, u32, u8> {
+    /// union_explicit returns the number of registers that were raised.
+    pub fn union_explicit(&mut self, explicit_storage: &ExplicitStorage) -> u32 {
+        explicit_storage.iter().filter(|i| self.set(*i)).count() as u32
+    }
+
+    /// prune_zeros removes entries with a value of 0.  They carry no information but still take up
+    /// space and count toward the sparse threshold.
+    pub fn prune_zeros(&mut self) {
+        self.buf.retain(|(_, value)| *value != 0);
+    }
+
+    pub fn iter(&self) -> Iter<'_, (u32, u8)> {
         self.buf.iter()
     }
 }
 
 impl Registers for SparseRegisters {
-    fn set_if_greater(&mut self, reg_num: u32, value: u8) {
+    fn set_if_greater(&mut self, reg_num: RegisterIndex, value: RegisterValue) -> bool {
+        let (reg_num, value) = (reg_num.0, value.0);
         let value = value & (self.settings.m_bits_mask as u8);
 
-        match self.buf.entry(reg_num) {
-            Entry::Vacant(entry) => {
-                entry.insert(value);
+        match self.buf.binary_search_by_key(&reg_num, |(k, _)| *k) {
+            Err(i) => {
+                self.buf.insert(i, (reg_num, value));
+                true
             }
-            Entry::Occupied(mut occupied_entry) => {
-                if *occupied_entry.get() < value {
-                    occupied_entry.insert(value);
+            Ok(i) => {
+                if self.buf[i].1 < value {
+                    self.buf[i].1 = value;
+                    return true;
                 }
+
+                false
             }
-        };
+        }
+    }
+
+    #[cfg(feature = "touch-counts")]
+    fn record_touch(&mut self, reg_num: RegisterIndex) {
+        *self.touches.0.entry(reg_num.0).or_default() += 1;
     }
 
     fn indicator(&self) -> (f64, u32) {
         let mut sum: f64 = 0.0;
 
         for (_, v) in self.buf.iter() {
-            sum += 1.0 / ((1_u64 << v) as f64)
+            sum += inverse_pow2(*v)
         }
 
         let number_of_zeros = ((1 << self.settings.log_2m) - self.buf.len()) as u32;
@@ -110,11 +216,8 @@ impl Registers for SparseRegisters {
 impl Storage for SparseRegisters {
     fn bytes_size(&self) -> usize {
         divide_by_8_round_up(
-            (self.settings.log_2m + self.settings.reg_width)
-                * TryInto::<u32>::try_into(self.buf.len()).unwrap(),
+            (self.settings.log_2m + self.settings.reg_width) as usize * self.buf.len(),
         )
-        .try_into()
-        .unwrap()
     }
 
     fn to_bytes(&self, buf: &mut [u8]) {
@@ -135,31 +238,65 @@ impl Storage for SparseRegisters {
         }
     }
 
-    fn from_bytes(settings: &Settings, buf: &[u8]) -> Self {
-        let bits_per_register = (settings.log_2m + settings.reg_width) as u8;
-        let mut offset: u32 = 0;
-        let max_offset = buf.len() as u32 * u8::BITS;
+    fn from_bytes(settings: &Settings, buf: &[u8]) -> Result<Self, HllError> {
+        // entries are read as u32, which is too narrow for the largest log_2m and reg_width
+        let bits_per_register = settings.log_2m + settings.reg_width;
+        if bits_per_register > u32::BITS {
+            return Err(HllError::SparseEntryWidth(bits_per_register));
+        }
+
+        let bits_per_register = bits_per_register as u8;
+        let mut offset: usize = 0;
+        let max_offset = buf.len() * u8::BITS as usize;
         let reg_mask: u32 = (1 << settings.reg_width) - 1;
         let reg_num_mask: u32 = !reg_mask;
 
         let mut res = Self::with_settings(settings);
-        let mut i = 0;
-        while (offset + bits_per_register as u32) <= max_offset {
-            let (idx, pos) = calc_position(i, bits_per_register);
-            let value = read_bits(buf, idx, pos, bits_per_register);
+        while (offset + bits_per_register as usize) <= max_offset {
+            let (idx, pos) = (offset / 8, (offset % 8) as u8);
+            let value = try_read_bits(buf, idx, pos, bits_per_register)?;
             let reg_num = (value & reg_num_mask) >> settings.reg_width;
-            let reg_value = value & reg_mask;
+            let reg_value = (value & reg_mask) as u8;
+
+            res.buf.push((reg_num, reg_value));
+
+            offset += bits_per_register as usize;
+        }
+
+        // anything after the last whole entry must be padding to the next byte, so a whole byte or
+        // more left over means the payload was cut off partway through an entry
+        if max_offset - offset >= u8::BITS as usize {
+            return Err(HllError::StorageLength {
+                type_id: 3,
+                len: buf.len(),
+            });
+        }
+
+        // entries narrower than a byte can leave room for a whole entry in the trailing padding
+        // bits, which decodes as register 0 with a value of 0. keep the max of any duplicate
+        // registers so it can't overwrite a real register 0 and prune it below.
+        res.buf.sort_unstable();
+        res.buf.dedup_by(|next, prev| {
+            if next.0 != prev.0 {
+                return false;
+            }
 
-            res.buf.insert(reg_num, reg_value as u8);
+            prev.1 = prev.1.max(next.1);
+            true
+        });
 
-            offset += bits_per_register as u32;
-            i += 1;
+        let max_value = settings.max_hashed_register_value();
+        if let Some((_, value)) = res.buf.iter().find(|(_, value)| *value > max_value) {
+            return Err(HllError::RegisterValue(*value));
         }
 
-        res
+        res.prune_zeros();
+        Ok(res)
     }
 
     fn clear(&mut self) {
         self.buf.clear();
+        #[cfg(feature = "touch-counts")]
+        self.touches.0.clear();
     }
 }
@@ -1,6 +1,6 @@
 use crate::settings::Settings;
 use crate::sparse::SparseRegisters;
-use crate::{Registers, Storage};
+use crate::{RegisterIndex, RegisterValue, Registers, Storage};
 use std::collections::BTreeMap;
 
 fn sparse_test_settings() -> Settings {
@@ -15,7 +15,11 @@ pub(crate) fn construct_hll_value(log2m: u32, register: u32, value: u32) -> u64
 }
 
 fn assert_register_present(registers: &SparseRegisters, register: u32, value: u8) {
-    assert_eq!(registers.buf.get(&register), Some(&value));
+    assert_eq!(registers.get(register), Some(value));
+}
+
+fn registers_map(registers: &SparseRegisters) -> BTreeMap<u32, u8> {
+    registers.iter().copied().collect()
 }
 
 fn assert_one_register_set(registers: &SparseRegisters, register: u32, value: u8) {
@@ -132,7 +136,10 @@ fn test_add_sparse() {
         registers.set(raw_value);
 
         let register_value2 = 1;
-        registers.set_if_greater(register_index, register_value2 as u8);
+        registers.set_if_greater(
+            RegisterIndex(register_index),
+            RegisterValue(register_value2 as u8),
+        );
 
         assert_one_register_set(&registers, register_index, register_value as u8);
     }
@@ -143,9 +150,9 @@ fn test_union_sparse() {
     // Test two disjoint multisets should union properly
     {
         let mut registers_a = SparseRegisters::with_settings(&sparse_test_settings());
-        registers_a.set_if_greater(1, 1);
+        registers_a.set_if_greater(RegisterIndex(1), RegisterValue(1));
         let mut registers_b = SparseRegisters::with_settings(&sparse_test_settings());
-        registers_b.set_if_greater(2, 1);
+        registers_b.set_if_greater(RegisterIndex(2), RegisterValue(1));
 
         registers_a.union_sparse(&registers_b);
 
@@ -157,9 +164,9 @@ fn test_union_sparse() {
     // Test two exactly overlapping multisets should union properly
     {
         let mut registers_a = SparseRegisters::with_settings(&sparse_test_settings());
-        registers_a.set_if_greater(1, 10);
+        registers_a.set_if_greater(RegisterIndex(1), RegisterValue(10));
         let mut registers_b = SparseRegisters::with_settings(&sparse_test_settings());
-        registers_b.set_if_greater(1, 13);
+        registers_b.set_if_greater(RegisterIndex(1), RegisterValue(13));
 
         registers_a.union_sparse(&registers_b);
 
@@ -173,14 +180,14 @@ fn test_union_sparse() {
         let mut registers_b = SparseRegisters::with_settings(&sparse_test_settings());
 
         // register index = 3
-        registers_a.set_if_greater(3, 11);
+        registers_a.set_if_greater(RegisterIndex(3), RegisterValue(11));
 
         // register index = 4
-        registers_a.set_if_greater(4, 13);
-        registers_b.set_if_greater(4, 21);
+        registers_a.set_if_greater(RegisterIndex(4), RegisterValue(13));
+        registers_b.set_if_greater(RegisterIndex(4), RegisterValue(21));
 
         // register index = 5
-        registers_b.set_if_greater(5, 14);
+        registers_b.set_if_greater(RegisterIndex(5), RegisterValue(14));
 
         registers_a.union_sparse(&registers_b);
 
@@ -199,7 +206,7 @@ fn test_to_from_bytes_sparse() {
         let mut buf = vec![0u8; registers.bytes_size()];
         registers.to_bytes(&mut buf);
 
-        let from_bytes = SparseRegisters::from_bytes(&sparse_test_settings(), &buf);
+        let from_bytes = SparseRegisters::from_bytes(&sparse_test_settings(), &buf).unwrap();
         assert_eq!(from_bytes.buf.len(), 0);
     }
 
@@ -207,13 +214,13 @@ fn test_to_from_bytes_sparse() {
     {
         let mut registers = SparseRegisters::with_settings(&sparse_test_settings());
         for i in 0..3 {
-            registers.set_if_greater(i, (i + 9) as u8);
+            registers.set_if_greater(RegisterIndex(i), RegisterValue((i + 9) as u8));
         }
 
         let mut buf = vec![0u8; registers.bytes_size()];
         registers.to_bytes(&mut buf);
 
-        let from_bytes = SparseRegisters::from_bytes(&sparse_test_settings(), &buf);
+        let from_bytes = SparseRegisters::from_bytes(&sparse_test_settings(), &buf).unwrap();
         assert_elements_equal_sparse(&registers, &from_bytes);
     }
 
@@ -221,13 +228,13 @@ fn test_to_from_bytes_sparse() {
     {
         let mut registers = SparseRegisters::with_settings(&sparse_test_settings());
         for i in 0..sparse_test_settings().sparse_threshold.unwrap() as u32 {
-            registers.set_if_greater(i, ((i % 9) + 1) as u8);
+            registers.set_if_greater(RegisterIndex(i), RegisterValue(((i % 9) + 1) as u8));
         }
 
         let mut buf = vec![0u8; registers.bytes_size()];
         registers.to_bytes(&mut buf);
 
-        let from_bytes = SparseRegisters::from_bytes(&sparse_test_settings(), &buf);
+        let from_bytes = SparseRegisters::from_bytes(&sparse_test_settings(), &buf).unwrap();
         assert_elements_equal_sparse(&registers, &from_bytes);
     }
 }
@@ -248,7 +255,7 @@ fn test_random_values_sparse() {
         for _ in 0..100 {
             let register = rng.random_range(0..(1 << sparse_test_settings().log_2m));
             let value: u8 = rng.random_range(1..=31);
-            registers.set_if_greater(register, value);
+            registers.set_if_greater(RegisterIndex(register), RegisterValue(value));
 
             // Update expected map with max value
             expected
@@ -258,6 +265,97 @@ fn test_random_values_sparse() {
         }
 
         // Verify all values match
-        assert_eq!(registers.buf, expected);
+        assert_eq!(registers_map(&registers), expected);
+    }
+}
+
+#[test]
+fn test_to_from_bytes_sparse_trailing_padding() {
+    // entries of 5 and 7 bits leave enough trailing padding bits at some entry counts to hold a
+    // whole extra entry
+    for reg_width in [1, 3] {
+        let settings = Settings::new(4, reg_width, 0, true).unwrap();
+
+        for len in 1..=(1 << settings.log_2m) {
+            let mut registers = SparseRegisters::with_settings(&settings);
+            for i in 0..len {
+                registers.set_if_greater(RegisterIndex(i), RegisterValue(1));
+            }
+
+            let mut buf = vec![0u8; registers.bytes_size()];
+            registers.to_bytes(&mut buf);
+
+            let from_bytes = SparseRegisters::from_bytes(&settings, &buf).unwrap();
+            assert_eq!(len as usize, from_bytes.len(), "reg_width: {}", reg_width);
+            assert_elements_equal_sparse(&registers, &from_bytes);
+        }
+    }
+}
+
+#[test]
+fn test_from_bytes_sparse_truncated() {
+    let mut registers = SparseRegisters::with_settings(&sparse_test_settings());
+    for i in 0..20 {
+        registers.set_if_greater(RegisterIndex(i * 7), RegisterValue(((i % 9) + 1) as u8));
     }
+
+    let mut buf = vec![0u8; registers.bytes_size()];
+    registers.to_bytes(&mut buf);
+
+    // a buffer cut off partway through an entry is rejected, while one cut off between entries
+    // can't be told apart from a shorter set of registers
+    let bits_per_register =
+        (sparse_test_settings().log_2m + sparse_test_settings().reg_width) as usize;
+    for len in 0..buf.len() {
+        let res = SparseRegisters::from_bytes(&sparse_test_settings(), &buf[..len]);
+        if (len * 8) % bits_per_register >= 8 {
+            assert!(
+                matches!(res, Err(crate::HllError::StorageLength { type_id: 3, len: l }) if l == len),
+                "len: {}",
+                len
+            );
+            continue;
+        }
+
+        let from_bytes = res.unwrap();
+        assert!(from_bytes.len() < registers.len());
+        assert!(
+            from_bytes
+                .iter()
+                .all(|(reg_num, value)| registers.get(*reg_num) == Some(*value))
+        );
+    }
+}
+
+#[test]
+fn test_from_bytes_sparse_register_range() {
+    // 64 - 20 hash bits are left for the register values
+    let settings = Settings::new(20, 6, 0, true).unwrap();
+    let mut registers = SparseRegisters::with_settings(&settings);
+    registers.set_if_greater(RegisterIndex(3), RegisterValue(44));
+    registers.set_if_greater(RegisterIndex(9), RegisterValue(45));
+
+    let mut buf = vec![0u8; registers.bytes_size()];
+    registers.to_bytes(&mut buf);
+    assert!(matches!(
+        SparseRegisters::from_bytes(&settings, &buf),
+        Err(crate::HllError::RegisterValue(45))
+    ));
+}
+
+#[test]
+fn test_prune_zeros() {
+    let mut registers = SparseRegisters::with_settings(&sparse_test_settings());
+    registers.set_if_greater(RegisterIndex(1), RegisterValue(3));
+    registers.set_if_greater(RegisterIndex(5), RegisterValue(7));
+    let indicator = registers.indicator();
+
+    registers.set_if_greater(RegisterIndex(2), RegisterValue(0));
+    assert_eq!(3, registers.len());
+
+    registers.prune_zeros();
+    assert_eq!(2, registers.len());
+    assert_register_present(&registers, 1, 3);
+    assert_register_present(&registers, 5, 7);
+    assert_eq!(indicator, registers.indicator());
 }
@@ -1,4 +1,8 @@
-pub fn divide_by_8_round_up(i: u32) -> u32 {
+use crate::HllError;
+
+/// divide_by_8_round_up converts a bit count into the number of bytes needed to hold it.  It takes
+/// a usize since register bit counts (2^log_2m * reg_width) can exceed u32.
+pub fn divide_by_8_round_up(i: usize) -> usize {
     let mut result = i >> 3;
     if (i & 0x07) > 0 {
         result += 1;
@@ -6,10 +10,58 @@ pub fn divide_by_8_round_up(i: u32) -> u32 {
     result
 }
 
+/// INVERSE_POW2 holds 2^-v for every register value v that a 64 bit hash can produce.  It replaces a
+/// division per register in the indicator function.
+const INVERSE_POW2: [f64; 64] = {
+    let mut table = [0.0; 64];
+    let mut v = 0;
+    while v < 64 {
+        table[v] = 1.0 / (1_u64 << v) as f64;
+        v += 1;
+    }
+    table
+};
+
+/// inverse_pow2 returns 2^-value.  Values past 63 can only come from corrupt registers and are
+/// treated as 63.
+#[inline(always)]
+pub(crate) fn inverse_pow2(value: u8) -> f64 {
+    INVERSE_POW2[(value as usize).min(63)]
+}
+
+/// CRC32_TABLE holds the CRC of every byte value for the reflected IEEE polynomial used by zlib.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// crc32 returns the IEEE CRC32 (as used by zlib and gzip) of bytes.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0_u32, |crc, b| {
+        CRC32_TABLE[((crc ^ *b as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
 /// (idx, pos)
 #[inline(always)]
 pub(crate) fn calc_position(reg_num: u32, reg_width: u8) -> (usize, u8) {
-    let addr = reg_num * (reg_width as u32);
+    // the bit address of the last register overflows u32 for log_2m = 31 and reg_width > 1
+    let addr = reg_num as u64 * reg_width as u64;
     let idx = addr >> 3; /*divide by 8*/
     let pos = addr & 0x07; /*remainder 8*/
     (idx.try_into().unwrap(), pos.try_into().unwrap())
@@ -77,8 +129,8 @@ pub(crate) fn write_bits(buf: &mut [u8], idx: usize, pos: u8, value: u32, n_bits
     }
 
     let value_bytes = value.to_be_bytes();
-    let vbyte_cnt = divide_by_8_round_up(n_bits as u32);
-    let mut vidx = (4 - vbyte_cnt) as usize;
+    let vbyte_cnt = divide_by_8_round_up(n_bits as usize);
+    let mut vidx = 4 - vbyte_cnt;
 
     let mut pos = pos;
     let mut idx = idx;
@@ -103,8 +155,8 @@ pub(crate) fn write_bits(buf: &mut [u8], idx: usize, pos: u8, value: u32, n_bits
 #[inline(always)]
 pub(crate) fn read_bits(buf: &[u8], idx: usize, pos: u8, n_bits: u8) -> u32 {
     let mut value = 0_u32.to_be_bytes();
-    let vbyte_cnt = divide_by_8_round_up(n_bits as u32);
-    let mut vidx = (4 - vbyte_cnt) as usize;
+    let vbyte_cnt = divide_by_8_round_up(n_bits as usize);
+    let mut vidx = 4 - vbyte_cnt;
 
     let mut pos = pos;
     let mut idx = idx;
@@ -128,9 +180,66 @@ pub(crate) fn read_bits(buf: &[u8], idx: usize, pos: u8, n_bits: u8) -> u32 {
     u32::from_be_bytes(value)
 }
 
+/// check_bits_len returns `HllError::Truncated` if n_bits starting at bit pos of buf[idx] don't fit
+/// in buf.
+fn check_bits_len(buf: &[u8], idx: usize, pos: u8, n_bits: u8) -> Result<(), HllError> {
+    let expected = idx.saturating_add(divide_by_8_round_up(pos as usize + n_bits as usize));
+    if buf.len() < expected {
+        return Err(HllError::Truncated {
+            expected,
+            found: buf.len(),
+        });
+    }
+
+    Ok(())
+}
+
+/// try_read_bits is `read_bits` for positions that aren't known to be within buf, such as while
+/// parsing untrusted input.
+pub(crate) fn try_read_bits(buf: &[u8], idx: usize, pos: u8, n_bits: u8) -> Result<u32, HllError> {
+    check_bits_len(buf, idx, pos, n_bits)?;
+
+    Ok(read_bits(buf, idx, pos, n_bits))
+}
+
 #[cfg(test)]
 mod test {
-    use super::{read_bits, write_bits};
+    use super::{calc_position, divide_by_8_round_up, read_bits, try_read_bits, write_bits};
+    use crate::HllError;
+
+    #[test]
+    fn divide_by_8() {
+        assert_eq!(0, divide_by_8_round_up(0));
+        assert_eq!(1, divide_by_8_round_up(1));
+        assert_eq!(1, divide_by_8_round_up(8));
+        assert_eq!(2, divide_by_8_round_up(9));
+
+        // dense register bits for log_2m = 30, reg_width = 8 overflow u32
+        let (log_2m, reg_width) = (30, 8);
+        assert_eq!(1 << 30, divide_by_8_round_up(reg_width * (1 << log_2m)));
+        assert_eq!(
+            (1 << 29) + 1,
+            divide_by_8_round_up(reg_width * (1 << (log_2m - 1)) + 1)
+        );
+    }
+
+    #[test]
+    fn calc_position_max_register() {
+        assert_eq!((0, 0), calc_position(0, 8));
+        assert_eq!((1, 3), calc_position(1, 11));
+        // the last register of log_2m = 31 and reg_width = 8 starts past bit 2^34
+        assert_eq!(((1 << 31) - 1, 0), calc_position((1 << 31) - 1, 8));
+        assert_eq!(
+            ((((1 << 31) - 1) * 5) >> 3, 3),
+            calc_position((1 << 31) - 1, 5)
+        );
+    }
+
+    #[test]
+    fn crc32() {
+        assert_eq!(0, super::crc32(b""));
+        assert_eq!(0xcbf4_3926, super::crc32(b"123456789"));
+    }
 
     #[test]
     fn rw_bits() {
@@ -167,4 +276,20 @@ mod test {
         let mut buf = vec![0u8; 2];
         write_bits(&mut buf, 1, 7, 0xFFFF, 15)
     }
+
+    #[test]
+    fn try_read_bits_len() {
+        let buf = vec![0x01u8, 0xFE];
+        assert!(matches!(
+            try_read_bits(&buf, 1, 2, 7),
+            Err(HllError::Truncated {
+                expected: 3,
+                found: 2
+            })
+        ));
+        assert!(try_read_bits(&buf, 2, 0, 1).is_err());
+
+        assert_eq!(0xFF, try_read_bits(&buf, 0, 7, 8).unwrap());
+        assert_eq!(0x3E, try_read_bits(&buf, 1, 2, 6).unwrap());
+    }
 }